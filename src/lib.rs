@@ -0,0 +1,5026 @@
+#[cfg(feature = "chrono")]
+use chrono::{Datelike, Duration as DateDuration, Local, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+#[cfg(feature = "chrono")]
+use std::fs;
+use std::io::{self, Read};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "xlsx")]
+mod xlsx;
+
+static SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// Default thresholds are expressed in Fahrenheit and converted to the selected scale
+// unless they are explicitly overridden on the command line.
+pub static DEFAULT_GDD_BASE_F: f32 = 65.0;
+pub static DEFAULT_FREEZE_THRESHOLD_F: f32 = 32.0;
+
+// The event that starts the cumulative gdd over from its day, e.g. when a crop is planted.
+pub static GDD_RESET_EVENT: &str = "reset";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TemperatureScale {
+    #[default]
+    Fahrenheit,
+    Celsius,
+}
+
+impl TemperatureScale {
+    // Converts a Fahrenheit value into this scale.
+    pub fn convert_fahrenheit(self, value: f32) -> f32 {
+        match self {
+            TemperatureScale::Fahrenheit => value,
+            TemperatureScale::Celsius => (value - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    // Converts a value in this scale into Celsius.
+    pub fn to_celsius(self, value: f32) -> f32 {
+        match self {
+            TemperatureScale::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            TemperatureScale::Celsius => value,
+        }
+    }
+
+    // Converts a Celsius value into this scale.
+    pub fn convert_celsius(self, value: f32) -> f32 {
+        match self {
+            TemperatureScale::Fahrenheit => value * 9.0 / 5.0 + 32.0,
+            TemperatureScale::Celsius => value,
+        }
+    }
+
+    // Converts a number of degree days in this scale into `target`'s. Unlike temperatures these
+    // are differences, so only the 1.8 factor between the degree sizes applies, not the offset.
+    pub fn degree_days_in(self, degree_days: f32, target: TemperatureScale) -> f32 {
+        match (self, target) {
+            (TemperatureScale::Fahrenheit, TemperatureScale::Celsius) => degree_days / 1.8,
+            (TemperatureScale::Celsius, TemperatureScale::Fahrenheit) => degree_days * 1.8,
+            _ => degree_days,
+        }
+    }
+
+    // Converts a value in this scale into Fahrenheit.
+    pub fn to_fahrenheit(self, value: f32) -> f32 {
+        match self {
+            TemperatureScale::Fahrenheit => value,
+            TemperatureScale::Celsius => value * 9.0 / 5.0 + 32.0,
+        }
+    }
+}
+
+// Vapor pressure deficit (kPa) from air temperature (°C) and relative humidity (%), using the
+// Tetens equation for saturation vapor pressure. Negative when relative humidity exceeds 100%.
+pub fn vapor_pressure_deficit(temperature_c: f32, humidity: f32) -> f32 {
+    let saturation_vapor_pressure = 0.6108 * (17.27 * temperature_c / (temperature_c + 237.3)).exp();
+    saturation_vapor_pressure * (1.0 - humidity / 100.0)
+}
+
+// The dew point in Celsius, by the Magnus formula with the same constants as the Tetens equation
+// used for vpd.
+pub fn dew_point(temperature_c: f32, humidity: f32) -> f32 {
+    let gamma = (humidity / 100.0).ln() + 17.27 * temperature_c / (temperature_c + 237.3);
+    237.3 * gamma / (17.27 - gamma)
+}
+
+// What the air feels like: the NWS wind chill when it is at most 50 °F with wind above 3 mph,
+// the Rothfusz heat index from 80 °F up, and the air temperature in between. Temperature is in
+// `scale`, relative humidity in %, and wind speed in mph (km/h for Celsius data).
+pub fn apparent_temperature(temperature: f32, humidity: f32, wind_speed: f32, scale: TemperatureScale) -> f32 {
+    let t = scale.to_fahrenheit(temperature);
+    let mph = match scale {
+        TemperatureScale::Fahrenheit => wind_speed,
+        TemperatureScale::Celsius => wind_speed / 1.609_344,
+    };
+    let apparent = if t <= 50.0 && mph > 3.0 {
+        let v = mph.powf(0.16);
+        35.74 + 0.6215 * t - 35.75 * v + 0.4275 * t * v
+    } else if t >= 80.0 {
+        let rh = humidity;
+        -42.379 + 2.049_015_2 * t + 10.143_331 * rh - 0.224_755_4 * t * rh - 0.006_837_83 * t * t
+            - 0.054_817_17 * rh * rh + 0.001_228_74 * t * t * rh + 0.000_852_82 * t * rh * rh - 0.000_001_99 * t * t * rh * rh
+    } else {
+        t
+    };
+    scale.convert_fahrenheit(apparent)
+}
+
+// The index of the optional wind speed column, recognized by a header containing "wind".
+pub fn wind_speed_column(header: &csv::StringRecord) -> Option<usize> {
+    header.iter().position(|name| name.to_lowercase().contains("wind"))
+}
+
+// The index of the dew point column, recognized by a header containing "dew". Failing that, an
+// input with at least the five usual columns has it fourth, whatever its header (e.g. `Td (°F)`).
+// Basic sensors that log only temperature and humidity have none.
+pub fn dew_point_column(header: &csv::StringRecord) -> Option<usize> {
+    header.iter().position(|name| name.to_lowercase().contains("dew")).or_else(|| positional_column(header, 3))
+}
+
+// The index of the vpd column, recognized by a header containing "vpd", or else fifth in an input
+// with at least the five usual columns.
+pub fn vpd_column(header: &csv::StringRecord) -> Option<usize> {
+    header.iter().position(|name| name.to_lowercase().contains("vpd")).or_else(|| positional_column(header, 4))
+}
+
+// The column at `index` of the usual layout (timestamp, temperature, humidity, dew point, vpd),
+// when the input's fourth and fifth columns both fit that layout: each is named for its reading
+// or at least not recognizably another column. So a `rain` column after a wind speed one isn't
+// taken for the vpd.
+fn positional_column(header: &csv::StringRecord, index: usize) -> Option<usize> {
+    let fits = |index: usize, keyword: &str| header.get(index).is_some_and(|name| {
+        let name = name.to_lowercase();
+        name.contains(keyword) || !["dew", "vpd", "wind"].iter().any(|other| name.contains(other))
+    });
+    (fits(3, "dew") && fits(4, "vpd")).then_some(index)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum HumidityScale {
+    #[default]
+    Percent,
+    Fraction,
+}
+
+impl FromStr for HumidityScale {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "percent" => Ok(HumidityScale::Percent),
+            "fraction" => Ok(HumidityScale::Fraction),
+            _ => Err(format!("Unknown humidity scale: {}", value)),
+        }
+    }
+}
+
+impl HumidityScale {
+    // Relative humidity is always summarized as a percentage.
+    pub fn to_percent(self, value: f32) -> f32 {
+        match self {
+            HumidityScale::Percent => value,
+            HumidityScale::Fraction => value * 100.0,
+        }
+    }
+}
+
+// Which side of the base temperature degree-days accumulate on: `Above` for growing/cooling
+// degree days, `Below` for heating degree days.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DegreeDayDirection {
+    #[default]
+    Above,
+    Below,
+}
+
+impl FromStr for DegreeDayDirection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "above" => Ok(DegreeDayDirection::Above),
+            "below" => Ok(DegreeDayDirection::Below),
+            _ => Err(format!("Unknown degree day direction: {}", value)),
+        }
+    }
+}
+
+// How a day's growing degree days are computed from its temperatures: `Mean` takes the mean of
+// every reading less the base; `MinMax` averages the day's max and min and floors the result at
+// zero; `BaseFloor` raises a max or min below the base up to it before averaging, so a cold
+// night can't cancel out a warm afternoon; `Hourly` integrates the degree-hours above the base
+// between consecutive readings and divides by the 24 hours of a day, and falls back to `MinMax` for
+// days without at least two readings to integrate between; `Modified` clamps the max and min to
+// the degree-day caps before averaging, as for corn's 86°F/50°F, and floors the result at zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GddMethod {
+    #[default]
+    Mean,
+    MinMax,
+    BaseFloor,
+    Hourly,
+    Modified,
+}
+
+impl FromStr for GddMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "mean" => Ok(GddMethod::Mean),
+            "min-max" => Ok(GddMethod::MinMax),
+            "base-floor" => Ok(GddMethod::BaseFloor),
+            "hourly" => Ok(GddMethod::Hourly),
+            "modified" => Ok(GddMethod::Modified),
+            _ => Err(format!("Unknown gdd method: {}", value)),
+        }
+    }
+}
+
+// The method's name as given to `--gdd-method`.
+impl fmt::Display for GddMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GddMethod::Mean => write!(f, "mean"),
+            GddMethod::MinMax => write!(f, "min-max"),
+            GddMethod::BaseFloor => write!(f, "base-floor"),
+            GddMethod::Hourly => write!(f, "hourly"),
+            GddMethod::Modified => write!(f, "modified"),
+        }
+    }
+}
+
+// Crop presets for the gdd base and the degree-day caps, so a run can be set up with `--crop`.
+// Explicit `--gdd-base` and `--degree-day-cap-*` flags take precedence over the preset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crop {
+    Corn,
+    Soybean,
+    Wheat,
+}
+
+impl FromStr for Crop {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "corn" => Ok(Crop::Corn),
+            "soybean" => Ok(Crop::Soybean),
+            "wheat" => Ok(Crop::Wheat),
+            _ => Err(format!("Unknown crop: {}", value)),
+        }
+    }
+}
+
+impl Crop {
+    // The crop's gdd base, in Fahrenheit.
+    pub fn gdd_base_f(self) -> f32 {
+        match self {
+            Crop::Corn | Crop::Soybean => 50.0,
+            Crop::Wheat => 32.0,
+        }
+    }
+
+    // The crop's low and high degree-day caps, in Fahrenheit.
+    pub fn caps_f(self) -> (Option<f32>, Option<f32>) {
+        match self {
+            Crop::Corn | Crop::Soybean => (Some(50.0), Some(86.0)),
+            Crop::Wheat => (None, None),
+        }
+    }
+}
+
+// How a percentile is read off a day's sorted readings, to match whichever tool results are
+// checked against: `Nearest` takes the reading at rank ceil(p·n); `Linear` interpolates at rank
+// (n - 1)·p + 1, like Excel's PERCENTILE.INC and numpy's default; `Hazen` interpolates at rank
+// n·p + 0.5, clamped to the first and last readings.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PercentileMethod {
+    Nearest,
+    #[default]
+    Linear,
+    Hazen,
+}
+
+impl FromStr for PercentileMethod {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "nearest" => Ok(PercentileMethod::Nearest),
+            "linear" => Ok(PercentileMethod::Linear),
+            "hazen" => Ok(PercentileMethod::Hazen),
+            _ => Err(format!("Unknown percentile method: {}", value)),
+        }
+    }
+}
+
+impl PercentileMethod {
+    // The `fraction` (0 to 1) percentile of readings sorted in ascending order; NaN without any.
+    pub fn percentile(self, sorted: &[f32], fraction: f64) -> f32 {
+        if sorted.is_empty() {
+            return f32::NAN;
+        }
+        let count = sorted.len() as f64;
+        // One-based and fractional, for the interpolating methods.
+        let rank = match self {
+            PercentileMethod::Nearest => return sorted[((fraction * count).ceil() as usize).clamp(1, sorted.len()) - 1],
+            PercentileMethod::Linear => (count - 1.0) * fraction + 1.0,
+            PercentileMethod::Hazen => (count * fraction + 0.5).clamp(1.0, count),
+        };
+        let below = rank.floor() as usize;
+        let above = (below + 1).min(sorted.len());
+        (sorted[below - 1] as f64 + (sorted[above - 1] - sorted[below - 1]) as f64 * (rank - below as f64)) as f32
+    }
+}
+
+// How `DaySummaries::resample_daily` fills a missing day: `None` leaves its stats missing (NaN);
+// `Forward` repeats the previous day's; `Interpolate` interpolates linearly between the days on
+// either side of the gap.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FillPolicy {
+    #[default]
+    None,
+    Forward,
+    Interpolate,
+}
+
+impl FromStr for FillPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "none" => Ok(FillPolicy::None),
+            "forward" => Ok(FillPolicy::Forward),
+            "interpolate" => Ok(FillPolicy::Interpolate),
+            _ => Err(format!("Unknown fill policy: {}", value)),
+        }
+    }
+}
+
+// A sensor's logging interval, given as a number with an `s`, `m`, or `h` suffix (e.g. `15m`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval(pub Duration);
+
+impl FromStr for Interval {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (amount, unit_seconds) = [('s', 1), ('m', 60), ('h', 60 * 60)]
+            .iter()
+            .find_map(|&(unit, unit_seconds)| value.strip_suffix(unit).map(|amount| (amount, unit_seconds)))
+            .ok_or_else(|| format!("Invalid interval unit: {}", value))?;
+        let seconds = amount.parse::<u64>().ok().and_then(|amount| amount.checked_mul(unit_seconds)).ok_or_else(|| format!("Invalid interval: {}", value))?;
+        if seconds == 0 {
+            return Err(format!("Interval must be positive: {}", value));
+        }
+
+        Ok(Interval(Duration::from_secs(seconds)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl FromStr for Granularity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "day" => Ok(Granularity::Day),
+            "week" => Ok(Granularity::Week),
+            "month" => Ok(Granularity::Month),
+            "year" => Ok(Granularity::Year),
+            _ => Err(format!("Unknown granularity: {}", value)),
+        }
+    }
+}
+
+// Identifies the bucket a date falls in. Weeks, months, and years carry their year so that e.g.
+// week 1 of two different years never collide; weeks use the ISO week-numbering year of the
+// week's (possibly shifted) start, and months and years use the crop year.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BucketKey {
+    Day(NaiveDate),
+    Week(i32, u32),
+    Month(i32, u32),
+    Year(i32),
+}
+
+// The agronomic calendar used for bucketing: the hour days start at, the weekday weeks start
+// on, and the month that starts the (crop) year.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calendar {
+    pub week_start: Weekday,
+    pub year_start: u32,
+    pub day_start_hour: u32,
+}
+
+#[cfg(feature = "chrono")]
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar { week_start: Weekday::Mon, year_start: 1, day_start_hour: 0 }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Calendar {
+    // The agronomic day a reading belongs to. With days starting at e.g. 06:00, readings before
+    // 06:00 count towards the previous day, so that a night isn't split across two days.
+    pub fn agronomic_date(&self, date: NaiveDate, seconds_of_day: u32) -> NaiveDate {
+        if seconds_of_day < self.day_start_hour * 3600 { date.pred() } else { date }
+    }
+
+    // The first day of the week containing `date`.
+    pub fn week_start_date(&self, date: NaiveDate) -> NaiveDate {
+        let days_into_week = (date.weekday().num_days_from_monday() + 7 - self.week_start.num_days_from_monday()) % 7;
+        date - DateDuration::days(days_into_week as i64)
+    }
+
+    // Crop years are named for the calendar year they start in.
+    pub fn crop_year(&self, date: NaiveDate) -> i32 {
+        if date.month() >= self.year_start { date.year() } else { date.year() - 1 }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Granularity {
+    pub fn bucket_key(self, date: NaiveDate, calendar: &Calendar) -> BucketKey {
+        match self {
+            Granularity::Day => BucketKey::Day(date),
+            Granularity::Week => {
+                let week = calendar.week_start_date(date).iso_week();
+                BucketKey::Week(week.year(), week.week())
+            },
+            Granularity::Month => BucketKey::Month(calendar.crop_year(date), date.month()),
+            Granularity::Year => BucketKey::Year(calendar.crop_year(date)),
+        }
+    }
+
+    // The first date of the bucket containing `date`, used as the bucket's label.
+    pub fn bucket_start(self, date: NaiveDate, calendar: &Calendar) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => calendar.week_start_date(date),
+            Granularity::Month => date.with_day(1).unwrap(),
+            Granularity::Year => NaiveDate::from_ymd(calendar.crop_year(date), calendar.year_start, 1),
+        }
+    }
+}
+
+// A range of candidate base temperatures, given as `min:max` or `min:max:step` (step defaults to 1).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BaseRange {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl FromStr for BaseRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts = value.split(':').map(|part| part.parse::<f32>()).collect::<Result<Vec<f32>, _>>().map_err(|_| format!("Invalid base range: {}", value))?;
+        let range = match parts.as_slice() {
+            [min, max] => BaseRange { min: *min, max: *max, step: 1.0 },
+            [min, max, step] => BaseRange { min: *min, max: *max, step: *step },
+            _ => return Err(format!("Invalid base range: {}", value)),
+        };
+        if range.step <= 0.0 || range.min > range.max {
+            return Err(format!("Invalid base range: {}", value));
+        }
+
+        Ok(range)
+    }
+}
+
+impl BaseRange {
+    pub fn bases(&self) -> impl Iterator<Item = f32> + '_ {
+        let count = ((self.max - self.min) / self.step).floor() as usize + 1;
+        (0..count).map(move |index| self.min + index as f32 * self.step)
+    }
+}
+
+// A window of the day, given as `START-END` in hours (`8-20`) or hours and minutes
+// (`08:00-20:00`). The end is exclusive, and a window whose end is before its start wraps past
+// midnight (e.g. `20-08` for the night).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HourWindow {
+    pub start: u32, // seconds after midnight.
+    pub end: u32, // seconds after midnight.
+}
+
+impl FromStr for HourWindow {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let seconds_of_day = |time: &str| {
+            let (hours, minutes) = time.trim().split_once(':').unwrap_or((time.trim(), "0"));
+            match (hours.parse::<u32>(), minutes.parse::<u32>()) {
+                (Ok(hours), Ok(minutes)) if (hours < 24 && minutes < 60) || (hours == 24 && minutes == 0) => Some(hours * 3600 + minutes * 60),
+                _ => None,
+            }
+        };
+        let window = value.split_once('-')
+            .and_then(|(start, end)| Some(HourWindow { start: seconds_of_day(start)?, end: seconds_of_day(end)? }))
+            .ok_or_else(|| format!("Invalid hour window: {}", value))?;
+        if window.start == window.end {
+            return Err(format!("Hour window is empty: {}", value));
+        }
+
+        Ok(window)
+    }
+}
+
+impl HourWindow {
+    pub fn contains(&self, seconds_of_day: u32) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&seconds_of_day)
+        } else {
+            seconds_of_day >= self.start || seconds_of_day < self.end
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GddBaseFit {
+    pub base: f32,
+    pub cv: f32, // coefficient of variation of the GDD accumulated between consecutive events.
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Csv,
+    Json, // a single array of the daily objects.
+    JsonLines, // one object per line, flushed as each day completes.
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "csv" => Ok(OutputFormat::Csv),
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::JsonLines),
+            _ => Err(format!("Unknown output format: {}", value)),
+        }
+    }
+}
+
+// How dates are written in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DateOutput {
+    #[default]
+    Naive,
+    Rfc3339,
+}
+
+impl FromStr for DateOutput {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "naive" => Ok(DateOutput::Naive),
+            "rfc3339" => Ok(DateOutput::Rfc3339),
+            _ => Err(format!("Unknown date output: {}", value)),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl DateOutput {
+    // Naive dates are written as `YYYY-MM-DD`; RFC 3339 as midnight UTC, `YYYY-MM-DDT00:00:00Z`.
+    pub fn format(self, date: NaiveDate) -> String {
+        match self {
+            DateOutput::Naive => date.to_string(),
+            DateOutput::Rfc3339 => date.format("%Y-%m-%dT00:00:00Z").to_string(),
+        }
+    }
+}
+
+// How a day's (or bucket's) events make up its event column.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum EventAggregation {
+    First,
+    #[default]
+    Last,
+    All,
+    Count,
+}
+
+impl FromStr for EventAggregation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "first" => Ok(EventAggregation::First),
+            "last" => Ok(EventAggregation::Last),
+            "all" => Ok(EventAggregation::All),
+            "count" => Ok(EventAggregation::Count),
+            _ => Err(format!("Unknown event aggregation: {}", value)),
+        }
+    }
+}
+
+impl EventAggregation {
+    // All events are joined with "; ", in the order they were listed.
+    pub fn apply(self, events: &[String]) -> String {
+        match self {
+            EventAggregation::First => events.first().cloned().unwrap_or_default(),
+            EventAggregation::Last => events.last().cloned().unwrap_or_default(),
+            EventAggregation::All => events.join("; "),
+            EventAggregation::Count => events.len().to_string(),
+        }
+    }
+}
+
+// When fields of the csv output are quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuoteStyle {
+    #[default]
+    Necessary,
+    Always,
+    Never,
+}
+
+impl FromStr for QuoteStyle {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "necessary" => Ok(QuoteStyle::Necessary),
+            "always" => Ok(QuoteStyle::Always),
+            "never" => Ok(QuoteStyle::Never),
+            _ => Err(format!("Unknown quote style: {}", value)),
+        }
+    }
+}
+
+impl QuoteStyle {
+    pub fn to_csv(self) -> csv::QuoteStyle {
+        match self {
+            QuoteStyle::Necessary => csv::QuoteStyle::Necessary,
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("Missing value for {}.", flag))?;
+    value.parse::<T>().map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub scale: TemperatureScale,
+    pub humidity_scale: HumidityScale,
+    pub gdd_base: Option<f32>,
+    pub extra_gdd_bases: Vec<f32>, // bases after the first in a `--gdd-base` list, e.g. `50,40`; each gets its own cumulative gdd column.
+    pub freeze_threshold: Option<f32>,
+    pub freeze_column: bool, // write whether each day's minimum temperature reached the freeze threshold.
+    #[cfg(feature = "chrono")]
+    pub max_date: Option<NaiveDate>,
+    pub drop_future: bool,
+    pub degree_day_base: Option<f32>,
+    pub degree_day_cap_low: Option<f32>,
+    pub degree_day_cap_high: Option<f32>,
+    pub degree_day_direction: DegreeDayDirection,
+    pub expected_interval: Option<Interval>,
+    pub append: bool,
+    #[cfg(feature = "chrono")]
+    pub explain: Option<NaiveDate>,
+    pub granularity: Granularity,
+    #[cfg(feature = "chrono")]
+    pub calendar: Calendar,
+    pub vpd_by_hour: bool,
+    pub report_errors: bool, // skip (and tally) rows that fail to parse or validate rather than stopping at the first.
+    pub clean_output: Option<String>,
+    pub rejects_output: Option<String>,
+    pub pivot: bool,
+    pub units_row: bool,
+    pub fit_gdd_base: Option<BaseRange>,
+    pub derive_vpd: bool,
+    pub output: Option<String>,
+    pub format: OutputFormat, // inferred from a `.json` or `.jsonl` output path when not given.
+    pub out_delimiter: Option<u8>, // the csv output's field delimiter; see `Config::out_delimiter`.
+    pub frost_dates: bool,
+    pub interpolate_days: bool,
+    pub max_interpolate_span: Option<i64>,
+    pub schema_check: bool,
+    pub expected_columns: Option<Vec<String>>,
+    pub cv: bool,
+    pub tolerate_truncation: bool,
+    pub events: Option<String>, // path of the events csv; each day lists the events dated within it, and a `GDD_RESET_EVENT` starts the cumulative gdd over.
+    pub sort_input: bool, // read all the rows and sort them by timestamp before summarizing, for input that isn't in chronological order.
+    pub na_values: Option<Vec<String>>,
+    pub season_report: bool,
+    pub annotations: Option<String>,
+    pub metrics: Vec<Arc<dyn SummaryMetric>>,
+    pub collapse_stable: Option<f32>,
+    // Keeps only every Nth valid record. Biases the stats when the data has a period that lines up
+    // with N, e.g. sampling every 60th record of minutely data always lands on the same minute.
+    pub sample_every: Option<usize>,
+    pub quote_style: QuoteStyle,
+    pub bounds: HashMap<Metric, Bounds>, // overrides of the default plausible bounds.
+    pub input: Option<String>,
+    pub compare: Option<String>,
+    pub dew_point_spread: bool,
+    pub date_output: DateOutput,
+    pub round: Option<usize>, // digits after the decimal point in numeric output; shortest exact form when unset.
+    pub group_by: Option<String>, // name of a label column; each distinct label is summarized separately.
+    pub gdd_both_units: bool, // also write the cumulative gdd in both Fahrenheit and Celsius degree days.
+    pub show_raw_gdd: bool, // also write each day's gdd before flooring at zero, and as it adds to the cumulative gdd, to show the effect of the configured method's floor.
+    pub gdd_target: Option<f32>, // gdd needed to reach a crop stage (e.g. silking); the cumulative gdd is also written as a percentage of it.
+    pub gdd_target_overshoot: bool, // let the percentage of `gdd_target` run past 100 rather than capping it there.
+    pub gdd_settings_columns: bool, // write the gdd method and base on every row, so rows from runs with different settings stay distinguishable.
+    pub metrics_only: bool, // write only the numeric columns, e.g. as a matrix for ML pipelines.
+    pub index_output: Option<String>, // with metrics_only, a file mapping each row number to its date.
+    pub no_output_header: bool, // leave out the header row, e.g. for outputs that get concatenated.
+    pub integrated_vpd: bool, // write each day's integrated vpd (kPa·hours), a proxy for transpiration demand.
+    pub clock_diagnostics: bool, // report backwards and stalled timestamps in the input.
+    pub event_aggregation: EventAggregation,
+    pub approx_percentiles: bool, // estimate medians in constant memory rather than keeping every reading.
+    pub exact_medians: bool, // with `approx_percentiles`, replace the estimated medians (and `percentile`) with exact ones from a second pass over the input.
+    pub gdd_method: GddMethod,
+    pub crop: Option<Crop>, // preset gdd base and degree-day caps, used unless set explicitly.
+    pub climatology: Option<String>, // path to write the day-of-year climatology to.
+    pub median: bool, // write each metric's median reading alongside its mean.
+    pub percentile: Option<f32>, // write each metric's readings at this percentile (0 to 100).
+    pub percentile_method: PercentileMethod,
+    pub calibration: Option<String>, // path of the calibration file, read into `calibrations`.
+    pub calibrations: HashMap<Metric, Calibration>, // corrections applied to each reading as it's parsed.
+    pub normalize: bool, // write the daily means as z-scores across the whole run instead of raw values.
+    pub date_format: Option<String>, // chrono format of the sensor timestamps, when not `YYYY-MM-DD HH:MM:SS`.
+    pub event_date_format: Option<String>, // format of the event and annotation timestamps; defaults to `date_format`.
+    pub fail_on_warning: bool, // exit with an error, before writing the summary, when the data drew any warnings.
+    pub flag_column: Option<String>, // name of a boolean column, e.g. rain or irrigation; each day records whether it was ever true.
+    pub round_dates_to: Option<Granularity>, // label each row with the start of its week or month, without changing the grouping.
+    pub ewma_alpha: Option<f32>, // smoothing factor of the exponentially weighted moving average of the daily mean temperature.
+    pub quality_column: Option<String>, // name of a per-reading quality flag column; readings not marked good are left out of the stats.
+    pub good_quality: Option<Vec<String>>, // the quality flags that mark a good reading, ignoring case; defaults to `DEFAULT_GOOD_QUALITY`.
+    pub watch: bool, // re-run the summary whenever the input file changes, resuming after the last complete day when only the csv output is written.
+    pub pre_aggregate: Option<Interval>, // average the readings over slots of this length (e.g. `1h`) before summarizing each day.
+    pub between_hours: Option<HourWindow>, // only readings taken within this window of each day contribute to the stats, e.g. lighting hours.
+    pub rails: HashMap<Metric, Bounds>, // the limits a metric's sensor clips at; readings exactly at one suggest saturation.
+    pub report_json: Option<String>, // path to write the `Report` of every requested analysis to, as JSON.
+    pub baseline: Option<String>, // path to day-of-year normals, as written by `--climatology`; each day's departure from them gets a column.
+    pub diurnal: Option<String>, // path to write the typical day to: each metric's mean by hour of day, across all days.
+    pub resume: bool, // continue an interrupted run from its checkpoint, appending the remaining days to the output; cumulative columns carry on from the checkpointed totals.
+    #[cfg(feature = "chrono")]
+    pub resume_after: Option<NaiveDate>, // readings in days (or buckets) up to this date are skipped; set from the checkpoint when resuming.
+}
+
+impl Config {
+    pub fn from_args<I: Iterator<Item = String>>(args: I) -> Result<Self, SensoryError> {
+        Config::parse_args(args).map_err(SensoryError::Config)
+    }
+
+    fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut format_given = false;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--fahrenheit" => config.scale = TemperatureScale::Fahrenheit,
+                "--celsius" => config.scale = TemperatureScale::Celsius,
+                "--gdd-base" => {
+                    let bases: String = parse_value(&arg, args.next())?;
+                    let mut bases = bases.split(',').map(|base| base.trim().parse::<f32>().map_err(|_| format!("Invalid value for --gdd-base: {}", base)));
+                    config.gdd_base = bases.next().transpose()?;
+                    config.extra_gdd_bases = bases.collect::<Result<_, _>>()?;
+                },
+                "--gdd-method" => config.gdd_method = parse_value(&arg, args.next())?,
+                "--crop" => config.crop = Some(parse_value(&arg, args.next())?),
+                "--freeze-threshold" => config.freeze_threshold = Some(parse_value(&arg, args.next())?),
+                "--freeze-column" => config.freeze_column = true,
+                #[cfg(feature = "chrono")]
+                "--max-date" => config.max_date = Some(parse_value(&arg, args.next())?),
+                "--drop-future" => config.drop_future = true,
+                "--degree-day-base" => config.degree_day_base = Some(parse_value(&arg, args.next())?),
+                "--degree-day-cap-low" => config.degree_day_cap_low = Some(parse_value(&arg, args.next())?),
+                "--degree-day-cap-high" => config.degree_day_cap_high = Some(parse_value(&arg, args.next())?),
+                "--degree-day-direction" => config.degree_day_direction = parse_value(&arg, args.next())?,
+                "--expected-interval" => config.expected_interval = Some(parse_value(&arg, args.next())?),
+                "--append" => config.append = true,
+                "--resume" => {
+                    config.resume = true;
+                    config.append = true;
+                },
+                "--no-output-header" => config.no_output_header = true,
+                #[cfg(feature = "chrono")]
+                "--explain" => config.explain = Some(parse_value(&arg, args.next())?),
+                "--granularity" => config.granularity = parse_value(&arg, args.next())?,
+                "--round-dates-to" => config.round_dates_to = Some(parse_value(&arg, args.next())?),
+                #[cfg(feature = "chrono")]
+                "--day-start-hour" => {
+                    config.calendar.day_start_hour = parse_value(&arg, args.next())?;
+                    if config.calendar.day_start_hour > 23 {
+                        return Err(format!("Invalid value for --day-start-hour: {}", config.calendar.day_start_hour));
+                    }
+                },
+                #[cfg(feature = "chrono")]
+                "--week-start" => config.calendar.week_start = parse_value(&arg, args.next())?,
+                #[cfg(feature = "chrono")]
+                "--year-start" => {
+                    config.calendar.year_start = parse_value(&arg, args.next())?;
+                    if !(1..=12).contains(&config.calendar.year_start) {
+                        return Err(format!("Invalid value for --year-start: {}", config.calendar.year_start));
+                    }
+                },
+                "--vpd-by-hour" => config.vpd_by_hour = true,
+                "--watch" => config.watch = true,
+                "--pre-aggregate" => config.pre_aggregate = Some(parse_value(&arg, args.next())?),
+                "--between-hours" => config.between_hours = Some(parse_value(&arg, args.next())?),
+                "--ewma-alpha" => {
+                    let alpha: f32 = parse_value(&arg, args.next())?;
+                    if !(alpha > 0.0 && alpha <= 1.0) {
+                        return Err(format!("Invalid value for --ewma-alpha: {} (expected a value in (0, 1])", alpha));
+                    }
+                    config.ewma_alpha = Some(alpha);
+                },
+                "--clock-diagnostics" => config.clock_diagnostics = true,
+                "--report-errors" => config.report_errors = true,
+                "--fail-on-warning" => config.fail_on_warning = true,
+                "--clean-output" => config.clean_output = Some(parse_value(&arg, args.next())?),
+                "--rejects-output" => config.rejects_output = Some(parse_value(&arg, args.next())?),
+                "--pivot" => config.pivot = true,
+                "--humidity-scale" => config.humidity_scale = parse_value(&arg, args.next())?,
+                "--units-row" => config.units_row = true,
+                "--derive-vpd" => config.derive_vpd = true,
+                "--events" => config.events = Some(parse_value(&arg, args.next())?),
+                "--compare" => config.compare = Some(parse_value(&arg, args.next())?),
+                "--input" => config.input = Some(parse_value(&arg, args.next())?),
+                "--output" => config.output = Some(parse_value(&arg, args.next())?),
+                "--format" => {
+                    config.format = parse_value(&arg, args.next())?;
+                    format_given = true;
+                },
+                "--out-delimiter" => {
+                    let delimiter: String = parse_value(&arg, args.next())?;
+                    config.out_delimiter = Some(match delimiter.as_str() {
+                        "tab" | "\\t" => b'\t',
+                        _ if delimiter.len() == 1 => delimiter.as_bytes()[0],
+                        _ => return Err(format!("Invalid value for --out-delimiter: {:?} (expected a single character or tab)", delimiter)),
+                    });
+                },
+                "--frost-dates" => config.frost_dates = true,
+                "--season-report" => config.season_report = true,
+                "--climatology" => config.climatology = Some(parse_value(&arg, args.next())?),
+                "--baseline" => config.baseline = Some(parse_value(&arg, args.next())?),
+                "--report-json" => config.report_json = Some(parse_value(&arg, args.next())?),
+                "--diurnal" => config.diurnal = Some(parse_value(&arg, args.next())?),
+                "--calibration" => config.calibration = Some(parse_value(&arg, args.next())?),
+                "--annotations" => config.annotations = Some(parse_value(&arg, args.next())?),
+                "--bounds" => {
+                    let MetricBounds(metric, bounds) = parse_value(&arg, args.next())?;
+                    config.bounds.insert(metric, bounds);
+                },
+                "--rails" => {
+                    let MetricBounds(metric, rails) = parse_value(&arg, args.next())?;
+                    config.rails.insert(metric, rails);
+                },
+                "--date-format" => config.date_format = Some(parse_value(&arg, args.next())?),
+                "--event-date-format" => config.event_date_format = Some(parse_value(&arg, args.next())?),
+                "--date-output" => config.date_output = parse_value(&arg, args.next())?,
+                "--round" => config.round = Some(parse_value(&arg, args.next())?),
+                "--group-by" => config.group_by = Some(parse_value(&arg, args.next())?),
+                "--event-agg" => config.event_aggregation = parse_value(&arg, args.next())?,
+                "--quote-style" => config.quote_style = parse_value(&arg, args.next())?,
+                "--sample-every" => {
+                    let sample_every = parse_value(&arg, args.next())?;
+                    if sample_every == 0 {
+                        return Err(format!("Invalid value for --sample-every: {}", sample_every));
+                    }
+                    config.sample_every = Some(sample_every);
+                },
+                "--collapse-stable" => config.collapse_stable = Some(parse_value(&arg, args.next())?),
+                "--metric" => {
+                    let metric: ColumnMetric = parse_value(&arg, args.next())?;
+                    config.metrics.push(Arc::new(metric));
+                },
+                "--interpolate-days" => config.interpolate_days = true,
+                "--schema-check" => config.schema_check = true,
+                "--cv" => config.cv = true,
+                "--median" => config.median = true,
+                "--percentile" => {
+                    let percentile: f32 = parse_value(&arg, args.next())?;
+                    if !(0.0..=100.0).contains(&percentile) {
+                        return Err(format!("Invalid value for --percentile: {} (expected 0 to 100)", percentile));
+                    }
+                    config.percentile = Some(percentile);
+                },
+                "--percentile-method" => config.percentile_method = parse_value(&arg, args.next())?,
+                "--normalize" => config.normalize = true,
+                "--approx-percentiles" => config.approx_percentiles = true,
+                "--exact-medians" => config.exact_medians = true,
+                "--gdd-both-units" => config.gdd_both_units = true,
+                "--show-raw-gdd" => config.show_raw_gdd = true,
+                "--gdd-settings-columns" => config.gdd_settings_columns = true,
+                "--gdd-target" => {
+                    let gdd_target: f32 = parse_value(&arg, args.next())?;
+                    if gdd_target <= 0.0 || gdd_target.is_nan() {
+                        return Err(format!("Invalid value for --gdd-target: {} (expected a positive number)", gdd_target));
+                    }
+                    config.gdd_target = Some(gdd_target);
+                },
+                "--gdd-target-overshoot" => config.gdd_target_overshoot = true,
+                "--metrics-only" => config.metrics_only = true,
+                "--index-output" => config.index_output = Some(parse_value(&arg, args.next())?),
+                "--dew-point-spread" => config.dew_point_spread = true,
+                "--integrated-vpd" => config.integrated_vpd = true,
+                "--flag-column" => config.flag_column = Some(parse_value(&arg, args.next())?),
+                "--quality-column" => config.quality_column = Some(parse_value(&arg, args.next())?),
+                "--good-quality" => {
+                    let good_quality: String = parse_value(&arg, args.next())?;
+                    config.good_quality = Some(good_quality.split(',').map(|flag| flag.trim().to_string()).collect());
+                },
+                "--tolerate-truncation" => config.tolerate_truncation = true,
+                "--sort-input" => config.sort_input = true,
+                "--na-values" => {
+                    let na_values: String = parse_value(&arg, args.next())?;
+                    config.na_values = Some(na_values.split(',').map(|na_value| na_value.trim().to_string()).collect());
+                },
+                "--expected-columns" => {
+                    let columns: String = parse_value(&arg, args.next())?;
+                    config.expected_columns = Some(columns.split(',').map(|column| column.trim().to_string()).collect());
+                    config.schema_check = true;
+                },
+                "--max-interpolate-span" => config.max_interpolate_span = Some(parse_value(&arg, args.next())?),
+                "--fit-gdd-base" => config.fit_gdd_base = Some(parse_value(&arg, args.next())?),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+        let output_extension = std::path::Path::new(&config.output_path()).extension().and_then(|extension| extension.to_str()).map(str::to_lowercase);
+        if !format_given {
+            match output_extension.as_deref() {
+                Some("json") => config.format = OutputFormat::Json,
+                Some("jsonl") => config.format = OutputFormat::JsonLines,
+                _ => {},
+            }
+        }
+
+        if config.out_delimiter.is_some() && config.format != OutputFormat::Csv {
+            return Err("--out-delimiter requires csv output.".to_string());
+        }
+        if config.pivot && config.append {
+            return Err("--pivot cannot be combined with --append.".to_string());
+        }
+        if config.pivot && config.no_output_header {
+            return Err("--pivot cannot be combined with --no-output-header.".to_string());
+        }
+        if config.format != OutputFormat::Csv && (config.pivot || config.append) {
+            return Err("--format json and jsonl cannot be combined with --pivot or --append.".to_string());
+        }
+        if config.input_path().to_lowercase().ends_with(".xls") {
+            return Err("Excel 97-2003 .xls workbooks are not supported; save the workbook as .xlsx or export the first sheet to csv.".to_string());
+        }
+        if config.input_is_xlsx() && !cfg!(feature = "xlsx") {
+            return Err("Reading .xlsx input needs the xlsx feature; rebuild with --features xlsx or export the first sheet to csv.".to_string());
+        }
+        if config.collapse_stable.is_some() && config.format != OutputFormat::Csv {
+            return Err("--collapse-stable requires csv output.".to_string());
+        }
+        if config.annotations.is_some() && (config.pivot || config.format != OutputFormat::Csv) {
+            return Err("--annotations requires csv output without --pivot.".to_string());
+        }
+        if config.interpolate_days && (config.granularity != Granularity::Day || config.format != OutputFormat::Csv) {
+            return Err("--interpolate-days requires daily granularity and csv output.".to_string());
+        }
+        if config.pre_aggregate.is_some_and(|interval| interval.0.as_secs() > SECONDS_PER_DAY) {
+            return Err("--pre-aggregate must be at most a day.".to_string());
+        }
+        if config.watch && (config.append || config.compare.is_some()) {
+            return Err("--watch cannot be combined with --append or --compare.".to_string());
+        }
+        if config.normalize && config.format != OutputFormat::Csv {
+            return Err("--normalize requires csv output.".to_string());
+        }
+        if (config.climatology.is_some() || config.baseline.is_some()) && config.granularity != Granularity::Day {
+            return Err("--climatology and --baseline require daily granularity.".to_string());
+        }
+        if config.gdd_target_overshoot && config.gdd_target.is_none() {
+            return Err("--gdd-target-overshoot requires --gdd-target.".to_string());
+        }
+        if config.exact_medians && (!config.approx_percentiles || config.format != OutputFormat::Csv) {
+            return Err("--exact-medians requires --approx-percentiles and csv output.".to_string());
+        }
+        if config.approx_percentiles && (config.cv || config.season_report) {
+            return Err("--approx-percentiles keeps no readings, so it cannot be combined with --cv or --season-report.".to_string());
+        }
+        if config.metrics_only && (config.format != OutputFormat::Csv || config.annotations.is_some()) {
+            return Err("--metrics-only requires csv output without --annotations.".to_string());
+        }
+        if config.index_output.is_some() && !config.metrics_only {
+            return Err("--index-output requires --metrics-only.".to_string());
+        }
+        if config.resume && config.group_by.is_some() {
+            return Err("--resume cannot be combined with --group-by.".to_string());
+        }
+        // The reports describe a single series of days, so they don't apply across groups.
+        if config.group_by.is_some() && (config.format != OutputFormat::Csv || config.annotations.is_some()
+            || config.frost_dates || config.season_report || config.climatology.is_some() || config.fit_gdd_base.is_some() || config.report_json.is_some() || config.diurnal.is_some()) {
+            return Err("--group-by requires csv output and cannot be combined with --annotations, --frost-dates, --season-report, --climatology, --fit-gdd-base, --report-json or --diurnal.".to_string());
+        }
+        #[cfg(feature = "chrono")]
+        if config.group_by.is_some() && config.explain.is_some() {
+            return Err("--group-by cannot be combined with --explain.".to_string());
+        }
+
+        Ok(config)
+    }
+
+    // Sets the temperature and humidity scales from a units row such as `,°C,%,°C,kPa`.
+    pub fn apply_units_row(&mut self, units: &csv::StringRecord) -> Result<(), SensoryError> {
+        let unit = |index: usize| units.get(index).unwrap_or("").trim().trim_start_matches('°').to_lowercase();
+
+        self.scale = match unit(1).as_str() {
+            "f" => TemperatureScale::Fahrenheit,
+            "c" => TemperatureScale::Celsius,
+            other => return Err(SensoryError::Schema(format!("Unknown temperature unit in units row: {:?}", other))),
+        };
+        self.humidity_scale = match unit(2).as_str() {
+            "%" => HumidityScale::Percent,
+            "fraction" | "0-1" => HumidityScale::Fraction,
+            other => return Err(SensoryError::Schema(format!("Unknown humidity unit in units row: {:?}", other))),
+        };
+
+        Ok(())
+    }
+
+    // The longest run of fully missing days that will be interpolated; defaults to a single day.
+    pub fn max_interpolate_span(&self) -> i64 {
+        self.max_interpolate_span.unwrap_or(1)
+    }
+
+    pub fn expected_columns(&self) -> Vec<String> {
+        self.expected_columns.clone().unwrap_or_else(|| DEFAULT_SCHEMA.iter().map(|column| column.to_string()).collect())
+    }
+
+    pub fn good_quality(&self) -> Vec<String> {
+        self.good_quality.clone().unwrap_or_else(|| DEFAULT_GOOD_QUALITY.iter().map(|flag| flag.to_string()).collect())
+    }
+
+    pub fn na_values(&self) -> Vec<String> {
+        self.na_values.clone().unwrap_or_else(|| DEFAULT_NA_VALUES.iter().map(|na_value| na_value.to_string()).collect())
+    }
+
+    // The plausible bounds of a metric's readings. The defaults are deliberately wide so that only
+    // sensor faults fall outside them; humidity goes up to 110% as slightly oversaturated readings
+    // are common (see `derive_vpd`).
+    pub fn bounds(&self, metric: Metric) -> Bounds {
+        self.bounds.get(&metric).copied().unwrap_or_else(|| match metric {
+            Metric::Temperature | Metric::DewPoint => Bounds { min: self.scale.convert_fahrenheit(-76.0), max: self.scale.convert_fahrenheit(158.0) },
+            Metric::Humidity => Bounds { min: 0.0, max: 110.0 },
+            Metric::Vpd => Bounds { min: 0.0, max: 10.0 },
+        })
+    }
+
+    pub fn input_path(&self) -> String {
+        self.input.clone().unwrap_or_else(|| "data/example.csv".to_string())
+    }
+
+    // Whether the input is an Excel workbook, which is read through `read_xlsx` rather than as csv.
+    pub fn input_is_xlsx(&self) -> bool {
+        self.input_path().to_lowercase().ends_with(".xlsx")
+    }
+
+    pub fn output_path(&self) -> String {
+        self.output.clone().unwrap_or_else(|| "data/out_example.csv".to_string())
+    }
+
+    // The csv output's field delimiter: as given, or else a tab for a `.tsv` output path and a
+    // comma for any other.
+    pub fn out_delimiter(&self) -> u8 {
+        let tsv = self.output_path().to_lowercase().ends_with(".tsv");
+        self.out_delimiter.unwrap_or(if tsv { b'\t' } else { b',' })
+    }
+
+    // Every metric summarized per day: the built-in ones in `Metric::ALL` order, then the custom
+    // ones in the order they were registered.
+    pub fn summary_metrics(&self) -> Vec<Arc<dyn SummaryMetric>> {
+        let degrees = match self.scale {
+            TemperatureScale::Fahrenheit => "°F",
+            TemperatureScale::Celsius => "°C",
+        };
+        let builtin = Metric::ALL.iter().map(|&metric| {
+            let unit = match metric {
+                Metric::Temperature | Metric::DewPoint => degrees,
+                Metric::Humidity => "%",
+                Metric::Vpd => "kPa",
+            };
+            Arc::new(BuiltinMetric { metric, unit: unit.to_string() }) as Arc<dyn SummaryMetric>
+        });
+        builtin.chain(self.metrics.iter().cloned()).collect()
+    }
+
+    pub fn gdd_base(&self) -> f32 {
+        let default_base_f = self.crop.map_or(DEFAULT_GDD_BASE_F, Crop::gdd_base_f);
+        self.gdd_base.unwrap_or_else(|| self.scale.convert_fahrenheit(default_base_f))
+    }
+
+    // The low and high degree-day caps: each explicit cap, or else the crop preset's.
+    pub fn degree_day_caps(&self) -> (Option<f32>, Option<f32>) {
+        let (preset_low, preset_high) = self.crop.map_or((None, None), Crop::caps_f);
+        let preset = |cap: Option<f32>| cap.map(|cap| self.scale.convert_fahrenheit(cap));
+        (self.degree_day_cap_low.or_else(|| preset(preset_low)), self.degree_day_cap_high.or_else(|| preset(preset_high)))
+    }
+
+    // The gdd base followed by any further bases from a `--gdd-base` list.
+    pub fn gdd_bases(&self) -> Vec<f32> {
+        std::iter::once(self.gdd_base()).chain(self.extra_gdd_bases.iter().copied()).collect()
+    }
+
+    pub fn event_date_format(&self) -> Option<&str> {
+        self.event_date_format.as_deref().or(self.date_format.as_deref())
+    }
+
+    pub fn freeze_threshold(&self) -> f32 {
+        self.freeze_threshold.unwrap_or_else(|| self.scale.convert_fahrenheit(DEFAULT_FREEZE_THRESHOLD_F))
+    }
+
+    // Records dated after this are considered future-dated; defaults to the system date.
+    #[cfg(feature = "chrono")]
+    pub fn max_date(&self) -> NaiveDate {
+        self.max_date.unwrap_or_else(|| Local::today().naive_local())
+    }
+
+    // A row's date as written in the output, snapped to the start of its period under `round_dates_to`.
+    #[cfg(feature = "chrono")]
+    pub fn date_label(&self, date: NaiveDate) -> String {
+        let date = self.round_dates_to.map_or(date, |period| period.bucket_start(date, &self.calendar));
+        self.date_output.format(date)
+    }
+}
+
+#[derive(Debug)]
+pub struct SensorRecord<T> {
+    pub timestamp: T,
+    pub seconds_of_day: u32, // time of the reading within its day, in seconds since midnight.
+    pub temperature: f32,
+    pub humidity: f32,
+    pub dew_point: f32,
+    pub vpd: f32,
+    pub custom: Vec<f32>, // readings of the registered custom metrics, NaN where missing.
+    pub apparent_temperature: f32, // from the wind speed column; NaN when the input has none.
+    pub flag: bool, // from the `flag_column`; false when there is none.
+}
+
+// The `.csv` files in a directory of daily files, in the order of the dates they're named by
+// (`YYYY-MM-DD.csv` or `YYYYMMDD.csv`). Any other `.csv` file name is an error.
+#[cfg(feature = "chrono")]
+pub fn daily_files(directory: &str) -> Result<Vec<std::path::PathBuf>, SensoryError> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| !extension.eq_ignore_ascii_case("csv")) {
+            continue;
+        }
+        let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("");
+        let date = NaiveDate::parse_from_str(stem, "%Y-%m-%d").or_else(|_| NaiveDate::parse_from_str(stem, "%Y%m%d"))
+            .map_err(|_| SensoryError::Schema(format!("File name {:?} in {} is not a date", path.file_name().unwrap_or_default(), directory)))?;
+        files.push((date, path));
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+// The raw sensor data at the configured input path, opened for reading from the start, with an
+// .xlsx workbook read as csv. A directory of daily files is read as one stream: the files are
+// read one after another in date order, keeping only the first file's header (and units row).
+// Nothing but an .xlsx workbook is held in memory, so each pass over the data opens it again.
+#[cfg(feature = "chrono")]
+pub fn open_input(config: &Config) -> Result<Box<dyn Read>, SensoryError> {
+    let path = config.input_path();
+    #[cfg(feature = "xlsx")]
+    {
+        if config.input_is_xlsx() {
+            return Ok(Box::new(io::Cursor::new(read_xlsx(&fs::read(&path)?)?)));
+        }
+    }
+    if !fs::metadata(&path)?.is_dir() {
+        return Ok(Box::new(fs::File::open(&path)?));
+    }
+    Ok(Box::new(DailyFiles {
+        files: daily_files(&path)?.into_iter(),
+        current: None,
+        header_lines: 0,
+        later_header_lines: 1 + config.units_row as usize,
+        ends_with_newline: true,
+    }))
+}
+
+// A directory of daily files read as one stream, a file at a time. A file that doesn't end in a
+// newline gets one, so that its last row doesn't run into the next file's first.
+#[cfg(feature = "chrono")]
+struct DailyFiles {
+    files: std::vec::IntoIter<std::path::PathBuf>,
+    current: Option<io::BufReader<fs::File>>,
+    // The header lines to skip at the start of the next file: none for the first.
+    header_lines: usize,
+    later_header_lines: usize,
+    ends_with_newline: bool,
+}
+
+#[cfg(feature = "chrono")]
+impl Read for DailyFiles {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        use std::io::BufRead;
+
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if self.current.is_none() {
+                let file = match self.files.next() {
+                    Some(file) => file,
+                    None => return Ok(0),
+                };
+                let mut reader = io::BufReader::new(fs::File::open(file)?);
+                for _ in 0..self.header_lines {
+                    reader.read_until(b'\n', &mut Vec::new())?;
+                }
+                self.header_lines = self.later_header_lines;
+                self.current = Some(reader);
+            }
+            let read = match &mut self.current {
+                Some(current) => current.read(buf)?,
+                None => 0,
+            };
+            if read > 0 {
+                self.ends_with_newline = buf[read - 1] == b'\n';
+                return Ok(read);
+            }
+            self.current = None;
+            if !self.ends_with_newline {
+                self.ends_with_newline = true;
+                buf[0] = b'\n';
+                return Ok(1);
+            }
+        }
+    }
+}
+
+// The csv reader takes `\r\n`, `\n` and a lone `\r` all as line endings, but a file edited across
+// platforms can still leave a carriage return at the end of a field (inside quotes, say), which
+// would keep it from parsing. Such trailing carriage returns are dropped.
+fn strip_carriage_returns(record: csv::StringRecord) -> csv::StringRecord {
+    if !record.iter().any(|field| field.ends_with('\r')) {
+        return record;
+    }
+    let mut stripped: csv::StringRecord = record.iter().map(|field| field.trim_end_matches('\r')).collect();
+    stripped.set_position(record.position().cloned());
+    stripped
+}
+
+// Tokens loggers commonly write in place of a missing reading.
+pub static DEFAULT_NA_VALUES: [&str; 6] = ["", "NA", "N/A", "NaN", "null", "-9999"];
+
+// Quality flags that loggers commonly write for a good reading.
+pub static DEFAULT_GOOD_QUALITY: [&str; 2] = ["OK", "GOOD"];
+
+// The default schema: each column's name must contain the corresponding keyword.
+pub static DEFAULT_SCHEMA: [&str; 5] = ["timestamp", "temperature", "humidity", "dew", "vpd"];
+
+// Validates that a header row has the expected columns in the expected order. Each expected
+// name must appear (case-insensitively) within the corresponding column's name. Returns a
+// readable diff of every mismatched column otherwise.
+pub fn check_schema(header: &csv::StringRecord, expected: &[String]) -> Result<(), SensoryError> {
+    let mut differences = Vec::new();
+    for index in 0..header.len().max(expected.len()) {
+        match (expected.get(index), header.get(index)) {
+            (Some(expected_name), Some(name)) if !name.to_lowercase().contains(&expected_name.to_lowercase()) => {
+                differences.push(format!("  column {}: expected {:?}, found {:?}", index + 1, expected_name, name));
+            },
+            (Some(expected_name), None) => differences.push(format!("  column {}: expected {:?}, found nothing", index + 1, expected_name)),
+            (None, Some(name)) => differences.push(format!("  column {}: unexpected column {:?}", index + 1, name)),
+            _ => {},
+        }
+    }
+
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(SensoryError::Schema(format!("Header does not match the expected schema:\n{}", differences.join("\n"))))
+    }
+}
+
+// SplitMix64: a tiny, fast pseudo-random generator, plenty for reproducible noise.
+#[cfg(feature = "chrono")]
+struct SplitMix64(u64);
+
+#[cfg(feature = "chrono")]
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniformly distributed in [-1, 1).
+    fn next_signed_unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32 * 2.0 - 1.0
+    }
+}
+
+// Settings for synthetic sensor data, for tests and demos: a reading every `interval` for `days`
+// days from `start`. Temperature (°F) follows a daily sinusoid around `mean_temperature` that
+// peaks at 15:00, humidity runs opposite to it, and both get uniform noise of up to `noise` from
+// a generator seeded with `seed`, so the same settings always produce the same data.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone)]
+pub struct Generator {
+    pub start: NaiveDate,
+    pub days: u32,
+    pub interval: Interval,
+    pub mean_temperature: f32,
+    pub amplitude: f32,
+    pub noise: f32,
+    pub seed: u64,
+    pub output: Option<String>,
+}
+
+#[cfg(feature = "chrono")]
+impl Default for Generator {
+    fn default() -> Self {
+        Generator {
+            start: NaiveDate::from_ymd(2021, 1, 1),
+            days: 7,
+            interval: Interval(Duration::from_secs(15 * 60)),
+            mean_temperature: 75.0,
+            amplitude: 10.0,
+            noise: 1.0,
+            seed: 0,
+            output: None,
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Generator {
+    pub fn from_args<I: Iterator<Item = String>>(args: I) -> Result<Self, SensoryError> {
+        Generator::parse_args(args).map_err(SensoryError::Config)
+    }
+
+    fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut generator = Generator::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--start" => generator.start = parse_value(&arg, args.next())?,
+                "--days" => generator.days = parse_value(&arg, args.next())?,
+                "--interval" => generator.interval = parse_value(&arg, args.next())?,
+                "--mean-temperature" => generator.mean_temperature = parse_value(&arg, args.next())?,
+                "--amplitude" => generator.amplitude = parse_value(&arg, args.next())?,
+                "--noise" => generator.noise = parse_value(&arg, args.next())?,
+                "--seed" => generator.seed = parse_value(&arg, args.next())?,
+                "--output" => generator.output = Some(parse_value(&arg, args.next())?),
+                _ => return Err(format!("Unknown argument: {}", arg)),
+            }
+        }
+
+        Ok(generator)
+    }
+
+    // Writes the readings as sensor csv, in the same layout the summaries are read from.
+    pub fn generate(&self, output: impl io::Write) -> Result<(), SensoryError> {
+        let mut rng = SplitMix64(self.seed);
+        let mut writer = csv::Writer::from_writer(output);
+        writer.write_record(["timestamp", "temperature (°F)", "relative humidity (%)", "dewpoint (°F)", "vpd (kPa)"])?;
+
+        let interval = self.interval.0.as_secs() as i64;
+        for reading in 0..(self.days as i64 * SECONDS_PER_DAY as i64 / interval) {
+            let timestamp = self.start.and_hms(0, 0, 0) + DateDuration::seconds(reading * interval);
+            let hours = (reading * interval) as f32 / 3600.0;
+            let phase = ((hours - 9.0) / 24.0 * 2.0 * std::f32::consts::PI).sin();
+
+            let temperature = self.mean_temperature + self.amplitude * phase + self.noise * rng.next_signed_unit();
+            let humidity = (50.0 - 15.0 * phase + self.noise * rng.next_signed_unit()).clamp(1.0, 100.0);
+            let temperature_c = TemperatureScale::Fahrenheit.to_celsius(temperature);
+            let dew_point = TemperatureScale::Fahrenheit.convert_celsius(dew_point(temperature_c, humidity));
+            let vpd = vapor_pressure_deficit(temperature_c, humidity);
+
+            writer.write_record(&[
+                timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                format!("{:.4}", temperature),
+                format!("{:.4}", humidity),
+                format!("{:.4}", dew_point),
+                format!("{:.4}", vpd),
+            ])?;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+// A row that failed to parse or validate, identified by its line in the input file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// How many rows a read summarized and how many it skipped, by reason. Rows that fail to parse
+// or validate are only skipped under `report_errors` (or as a truncated final record); otherwise
+// a bad row ends the read.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RowTally {
+    pub processed: usize,
+    pub parse_errors: usize,
+    pub out_of_range: usize,
+    pub flagged_bad: usize, // readings whose quality flag wasn't a good one.
+    pub out_of_order: usize, // readings for a day (or bucket) that was already completed.
+    pub sampled_out: usize, // valid readings passed over by `sample_every`.
+    pub outside_hours: usize, // valid readings outside the `between_hours` window.
+    pub dropped_future: usize, // readings dated after the max date, under `drop_future`.
+}
+
+impl RowTally {
+    pub fn skipped(&self) -> usize {
+        self.parse_errors + self.out_of_range + self.flagged_bad + self.out_of_order + self.sampled_out + self.outside_hours + self.dropped_future
+    }
+
+    pub fn rows(&self) -> usize {
+        self.processed + self.skipped()
+    }
+
+    pub fn add(&mut self, other: &RowTally) {
+        self.processed += other.processed;
+        self.parse_errors += other.parse_errors;
+        self.out_of_range += other.out_of_range;
+        self.flagged_bad += other.flagged_bad;
+        self.out_of_order += other.out_of_order;
+        self.sampled_out += other.sampled_out;
+        self.outside_hours += other.outside_hours;
+        self.dropped_future += other.dropped_future;
+    }
+
+    // The tally as a JSON object, for `Report::to_json`.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"rows\":{},\"processed\":{},\"skipped\":{},\"parse_errors\":{},\"out_of_range\":{},\"flagged_bad\":{},\"out_of_order\":{},\"sampled_out\":{},\"outside_hours\":{},\"dropped_future\":{}}}",
+            self.rows(), self.processed, self.skipped(), self.parse_errors, self.out_of_range, self.flagged_bad, self.out_of_order, self.sampled_out, self.outside_hours, self.dropped_future,
+        )
+    }
+}
+
+// Groups the digits in thousands, e.g. 10000 as "10,000".
+fn with_thousands_separators(count: usize) -> String {
+    let digits = count.to_string();
+    let mut grouped = String::new();
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+impl fmt::Display for RowTally {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Processed {} of {} rows; {} skipped ({} parse errors, {} out-of-range",
+            with_thousands_separators(self.processed),
+            with_thousands_separators(self.rows()),
+            with_thousands_separators(self.skipped()),
+            with_thousands_separators(self.parse_errors),
+            with_thousands_separators(self.out_of_range))?;
+        if self.flagged_bad > 0 {
+            write!(f, ", {} flagged bad", with_thousands_separators(self.flagged_bad))?;
+        }
+        if self.out_of_order > 0 {
+            write!(f, ", {} out of order", with_thousands_separators(self.out_of_order))?;
+        }
+        if self.sampled_out > 0 {
+            write!(f, ", {} sampled out", with_thousands_separators(self.sampled_out))?;
+        }
+        if self.outside_hours > 0 {
+            write!(f, ", {} outside the hours", with_thousands_separators(self.outside_hours))?;
+        }
+        if self.dropped_future > 0 {
+            write!(f, ", {} dated in the future", with_thousands_separators(self.dropped_future))?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl std::error::Error for RowError {}
+
+// Everything that can stop the sensor data from being processed, returned by every fallible
+// public function so downstream crates can use `?` on any of them.
+#[derive(Debug)]
+pub enum SensoryError {
+    Io(io::Error),
+    Csv(csv::Error),
+    Parse(RowError), // a sensor data row that didn't parse or was out of range.
+    Events(RowError),
+    Calibration(RowError),
+    Baseline(RowError),
+    Schema(String), // input whose header or units row isn't what it should be.
+    Config(String), // an invalid command line.
+    FailOnWarning(usize), // under `fail_on_warning`, the number of data quality warnings the run raised.
+}
+
+impl fmt::Display for SensoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SensoryError::Io(err) => write!(f, "Error reading input: {}", err),
+            SensoryError::Csv(err) => write!(f, "Error reading csv: {}", err),
+            SensoryError::Parse(err) => write!(f, "Error parsing sensor data: {}", err),
+            SensoryError::Events(err) => write!(f, "Error parsing events: {}", err),
+            SensoryError::Calibration(err) => write!(f, "Error parsing calibration: {}", err),
+            SensoryError::Baseline(err) => write!(f, "Error parsing baseline: {}", err),
+            SensoryError::Schema(message) | SensoryError::Config(message) => write!(f, "{}", message),
+            SensoryError::FailOnWarning(count) => write!(f, "Failing on {} data quality warning(s) (--fail-on-warning).", count),
+        }
+    }
+}
+
+impl std::error::Error for SensoryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SensoryError::Io(err) => Some(err),
+            SensoryError::Csv(err) => Some(err),
+            SensoryError::Parse(err) | SensoryError::Events(err) | SensoryError::Calibration(err) | SensoryError::Baseline(err) => Some(err),
+            SensoryError::Schema(_) | SensoryError::Config(_) | SensoryError::FailOnWarning(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SensoryError {
+    fn from(err: io::Error) -> Self {
+        SensoryError::Io(err)
+    }
+}
+
+// The input is read through the csv reader, so a failure to read it comes back as a csv error;
+// it's still an I/O error.
+impl From<csv::Error> for SensoryError {
+    fn from(err: csv::Error) -> Self {
+        if !err.is_io_error() {
+            return SensoryError::Csv(err);
+        }
+        match err.into_kind() {
+            csv::ErrorKind::Io(err) => SensoryError::Io(err),
+            _ => unreachable!(),
+        }
+    }
+}
+
+// The first worksheet of an .xlsx workbook as csv, with its columns named by the sheet's first row
+// as they would be in the csv export, so that it can be read like any other input.
+#[cfg(feature = "xlsx")]
+pub fn read_xlsx(workbook: &[u8]) -> Result<Vec<u8>, SensoryError> {
+    Ok(xlsx::first_sheet_as_csv(workbook)?)
+}
+
+// Reads the units row that directly follows the header, for inputs that have one.
+pub fn read_units_row(input: impl Read) -> Result<Option<csv::StringRecord>, SensoryError> {
+    Ok(csv::ReaderBuilder::new().flexible(true).from_reader(input).records().next().transpose()?)
+}
+
+// The date part of a `YYYY-MM-DD HH:MM:SS` timestamp.
+#[cfg(feature = "chrono")]
+fn parse_date_time(datetime: &str) -> Option<NaiveDate> {
+    let date_parts: Vec<&str> = datetime.split(' ').collect();
+    let date_vec: Vec<&str> = date_parts[0].split('-').collect();
+    if date_vec.len() != 3 {
+        return None;
+    }
+
+    NaiveDate::from_ymd_opt(
+        date_vec[0].parse::<i32>().ok()?,
+        date_vec[1].parse::<u32>().ok()?,
+        date_vec[2].parse::<u32>().ok()?,
+    )
+}
+
+// The time part of a `YYYY-MM-DD HH:MM:SS` timestamp, in seconds since midnight; midnight when
+// there's no time part. An hour past 23 or a minute or second past 59 is not a time, rather than
+// a time on the following day.
+#[cfg(feature = "chrono")]
+fn parse_seconds_of_day(datetime: &str) -> Option<u32> {
+    match datetime.split(' ').nth(1) {
+        Some(time) => {
+            let time_vec = time.split(':').map(|part| part.parse::<u32>().ok()).collect::<Option<Vec<u32>>>()?;
+            if time_vec.len() > 3 || time_vec.iter().zip([23, 59, 59].iter()).any(|(part, max)| part > max) {
+                return None;
+            }
+            Some(time_vec.iter().zip([3600, 60, 1].iter()).map(|(part, scale)| part * scale).sum())
+        },
+        None => Some(0),
+    }
+}
+
+// The date and time of day (in seconds since midnight) of a timestamp. Without a `format` it is
+// `YYYY-MM-DD HH:MM:SS`, or just the date; otherwise a chrono format string such as
+// `%d/%m/%Y %H:%M`, which may also leave out the time.
+#[cfg(feature = "chrono")]
+pub fn parse_timestamp(timestamp: &str, format: Option<&str>) -> Option<(NaiveDate, u32)> {
+    match format {
+        None => Some((parse_date_time(timestamp)?, parse_seconds_of_day(timestamp)?)),
+        Some(format) => match NaiveDateTime::parse_from_str(timestamp.trim(), format) {
+            Ok(date_time) => Some((date_time.date(), date_time.time().num_seconds_from_midnight())),
+            Err(_) => NaiveDate::parse_from_str(timestamp.trim(), format).ok().map(|date| (date, 0)),
+        },
+    }
+}
+
+// How `SensorRecord::try_from_csv_record` reads a row. Readings matching any of `na_values`
+// (case-insensitively) are missing (NaN), which the day stats then skip, and timestamps are in
+// `date_format` (see `parse_timestamp`). The dew point and vpd are read from the given columns
+// (see `dew_point_column` and `vpd_column`); without a column, that reading is missing.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseOptions {
+    pub na_values: Vec<String>,
+    pub date_format: Option<String>,
+    pub dew_point_column: Option<usize>,
+    pub vpd_column: Option<usize>,
+}
+
+// The `DEFAULT_NA_VALUES`, `YYYY-MM-DD HH:MM:SS` timestamps, and the dew point and vpd in the
+// fourth and fifth columns.
+#[cfg(feature = "chrono")]
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            na_values: DEFAULT_NA_VALUES.iter().map(|na_value| na_value.to_string()).collect(),
+            date_format: None,
+            dew_point_column: Some(3),
+            vpd_column: Some(4),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl SensorRecord<NaiveDate> {
+    pub fn from_csv_record(record: csv::StringRecord) -> Result<Self, SensoryError> {
+        Self::try_from_csv_record(&record, &ParseOptions::default())
+    }
+
+    pub fn try_from_csv_record(record: &csv::StringRecord, options: &ParseOptions) -> Result<Self, SensoryError> {
+        Self::parse_csv_record(record, options).map_err(SensoryError::Parse)
+    }
+
+    // Parses a record as `try_from_csv_record` does, failing with the bare row error that
+    // `process_group` hands to `ProcessObserver::on_reject`.
+    fn parse_csv_record(record: &csv::StringRecord, options: &ParseOptions) -> Result<Self, RowError> {
+        let ParseOptions { na_values, date_format, dew_point_column, vpd_column } = options;
+        let (dew_point_column, vpd_column) = (*dew_point_column, *vpd_column);
+        let row_error = |message: String| RowError {
+            line: record.position().map_or(0, |position| position.line()),
+            message,
+        };
+        let fields = dew_point_column.max(vpd_column).map_or(2, |column| column.max(2)) + 1;
+        if record.len() < fields {
+            return Err(row_error(format!("expected {} fields, found {}", fields, record.len())));
+        }
+        let parse_field = |index: Option<usize>, name: &str| {
+            let index = match index {
+                Some(index) => index,
+                None => return Ok(f32::NAN),
+            };
+            if na_values.iter().any(|na_value| na_value.eq_ignore_ascii_case(record[index].trim())) {
+                return Ok(f32::NAN);
+            }
+            record[index].parse::<f32>().map_err(|_| row_error(format!("invalid {}: {:?}", name, &record[index])))
+        };
+
+        let (timestamp, seconds_of_day) = match date_format.as_deref() {
+            None => (
+                parse_date_time(&record[0]).ok_or_else(|| row_error(format!("invalid date: {:?}", &record[0])))?,
+                parse_seconds_of_day(&record[0]).ok_or_else(|| row_error(format!("invalid time: {:?}", &record[0])))?,
+            ),
+            Some(format) => parse_timestamp(&record[0], Some(format)).ok_or_else(|| row_error(format!("invalid timestamp for format {:?}: {:?}", format, &record[0])))?,
+        };
+
+        Ok(SensorRecord {
+            timestamp,
+            seconds_of_day,
+            temperature: parse_field(Some(1), "temperature")?,
+            humidity: parse_field(Some(2), "humidity")?,
+            dew_point: parse_field(dew_point_column, "dew point")?,
+            vpd: parse_field(vpd_column, "vpd")?,
+            custom: Vec::new(),
+            apparent_temperature: f32::NAN,
+            flag: false,
+        })
+    }
+}
+
+// Days since the Unix epoch, for builds that only deal in epoch timestamps and don't pull in chrono.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EpochDay(pub i64);
+
+impl fmt::Display for EpochDay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl SensorRecord<EpochDay> {
+    pub fn from_epoch_csv_record(record: csv::StringRecord) -> Result<Self, SensoryError> {
+        Self::try_from_epoch_csv_record(&record)
+    }
+
+    // Parses a record whose timestamp column holds epoch seconds.
+    pub fn try_from_epoch_csv_record(record: &csv::StringRecord) -> Result<Self, SensoryError> {
+        let row_error = |message: String| SensoryError::Parse(RowError {
+            line: record.position().map_or(0, |position| position.line()),
+            message,
+        });
+        if record.len() < 5 {
+            return Err(row_error(format!("expected 5 fields, found {}", record.len())));
+        }
+        let parse_field = |index: usize, name: &str| record[index].parse::<f32>().map_err(|_| row_error(format!("invalid {}: {:?}", name, &record[index])));
+        let epoch_seconds = record[0].parse::<i64>().map_err(|_| row_error(format!("invalid epoch timestamp: {:?}", &record[0])))?;
+
+        Ok(SensorRecord {
+            timestamp: EpochDay(epoch_seconds.div_euclid(SECONDS_PER_DAY as i64)),
+            seconds_of_day: epoch_seconds.rem_euclid(SECONDS_PER_DAY as i64) as u32,
+            temperature: parse_field(1, "temperature")?,
+            humidity: parse_field(2, "humidity")?,
+            dew_point: parse_field(3, "dew point")?,
+            vpd: parse_field(4, "vpd")?,
+            custom: Vec::new(),
+            apparent_temperature: f32::NAN,
+            flag: false,
+        })
+    }
+}
+
+impl<T> SensorRecord<T> {
+    // The record's reading of each registered metric, in `Config::summary_metrics` order.
+    pub fn readings(&self) -> impl Iterator<Item = f32> + '_ {
+        Metric::ALL.iter().map(move |metric| metric.reading(self)).chain(self.custom.iter().copied())
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.seconds_of_day / 3600
+    }
+
+    // Whether any reading sits exactly at one of its metric's rails, where a sensor that clips
+    // would pin it. Such readings are in range, so bounds checks don't catch them.
+    pub fn at_rail(&self, rails: &HashMap<Metric, Bounds>) -> bool {
+        rails.iter().any(|(metric, rails)| {
+            let reading = metric.reading(self);
+            reading == rails.min || reading == rails.max
+        })
+    }
+
+    // Replaces the reading's vpd with one derived from its temperature and humidity. Relative
+    // humidity above 100% is bad data that would yield a physically impossible negative VPD, so
+    // the derived value is clamped at zero. Returns whether clamping occurred.
+    pub fn derive_vpd(&mut self, scale: TemperatureScale) -> bool {
+        let vpd = vapor_pressure_deficit(scale.to_celsius(self.temperature), self.humidity);
+        self.vpd = vpd.max(0.0);
+        vpd < 0.0
+    }
+
+    // Replaces the reading's dew point with one derived from its temperature and humidity.
+    pub fn derive_dew_point(&mut self, scale: TemperatureScale) {
+        self.dew_point = scale.convert_celsius(dew_point(scale.to_celsius(self.temperature), self.humidity));
+    }
+}
+
+impl<T> SensorRecord<T> {
+    // Checks each present reading against its metric's plausible bounds, failing with a parse
+    // error for the record's `line`. The vpd column is skipped when vpd is derived, as it is
+    // replaced anyway.
+    pub fn check_bounds(&self, config: &Config, line: u64) -> Result<(), SensoryError> {
+        for metric in Metric::ALL {
+            let reading = metric.reading(self);
+            let bounds = config.bounds(metric);
+            if (metric != Metric::Vpd || !config.derive_vpd) && (reading < bounds.min || reading > bounds.max) {
+                let message = format!("{} {} is outside the plausible range {}..{}", metric, reading, bounds.min, bounds.max);
+                return Err(SensoryError::Parse(RowError { line, message }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: PartialOrd> SensorRecord<T> {
+    // A misconfigured sensor clock can log readings dated in the future.
+    pub fn is_future(&self, max_date: T) -> bool {
+        self.timestamp > max_date
+    }
+}
+
+// Averages a metric by hour of day, pooling readings across all days.
+#[derive(Debug, Clone, Default)]
+pub struct HourOfDayProfile {
+    pub sums: [f64; 24],
+    pub counts: [u32; 24],
+}
+
+impl HourOfDayProfile {
+    pub fn add(&mut self, hour: u32, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+        self.sums[hour as usize] += value as f64;
+        self.counts[hour as usize] += 1;
+    }
+
+    // The mean for each hour of day, or None for hours without readings.
+    pub fn means(&self) -> Vec<Option<f32>> {
+        self.sums.iter().zip(self.counts.iter()).map(|(sum, count)| {
+            if *count == 0 { None } else { Some((sum / *count as f64) as f32) }
+        }).collect()
+    }
+
+    // The hour of day with the highest mean, e.g. the typically driest hour for VPD.
+    pub fn max_hour(&self) -> Option<(u32, f32)> {
+        self.hourly_means().max_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+    }
+
+    // The hour of day with the lowest mean, e.g. the typically wettest hour for VPD.
+    pub fn min_hour(&self) -> Option<(u32, f32)> {
+        self.hourly_means().min_by(|x, y| x.1.partial_cmp(&y.1).unwrap())
+    }
+
+    fn hourly_means(&self) -> impl Iterator<Item = (u32, f32)> {
+        self.means().into_iter().enumerate().filter_map(|(hour, mean)| mean.map(|mean| (hour as u32, mean)))
+    }
+}
+
+pub struct SensorDataset<T>(pub Vec<SensorRecord<T>>);
+
+impl<T: Copy + PartialOrd> SensorDataset<T> {
+    // Aggregates every reading in the inclusive date range into a single bucket dated `start`, its
+    // gdd computed with the configured method and caps. The readings aren't integrated over time,
+    // so the hourly method falls back to min-max as it does for a single reading.
+    // Returns None when no readings fall within the range.
+    pub fn stats_for_range(&self, start: T, end: T, config: &Config) -> Option<DaySummaryStats<T>> {
+        let mut records = self.0.iter().filter(|record| record.timestamp >= start && record.timestamp <= end);
+        let mut range_stats = DaySummaryStats::from_record(records.next()?, config.gdd_base());
+        range_stats.date = start;
+        for record in records {
+            range_stats.update(record, config.gdd_base());
+        }
+        range_stats.apply_gdd_method(config.gdd_method, config.gdd_base(), config.degree_day_caps());
+
+        Some(range_stats)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DaySummaries<T>(pub Vec<DaySummaryStats<T>>);
+
+impl<T> DaySummaries<T> {
+    // The summaries in chronological order.
+    pub fn iter(&self) -> std::slice::Iter<'_, DaySummaryStats<T>> {
+        self.0.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DaySummaries<T> {
+    type Item = &'a DaySummaryStats<T>;
+    type IntoIter = std::slice::Iter<'a, DaySummaryStats<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// A metric's overall mean and extremes, with the dates they occurred, across all summarized days.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeasonStats<T> {
+    pub metric: Metric,
+    pub mean: f32,
+    pub max: f32,
+    pub max_date: T,
+    pub min: f32,
+    pub min_date: T,
+}
+
+impl<T: fmt::Display> fmt::Display for DaySummaries<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        if let Some(first_day_summary) = self.0.first() {
+            if let Some(last_day_summary) = self.0.last() {
+                output += format!("{} records for date range {} - {}", self.0.len(), first_day_summary.date, last_day_summary.date).as_str();
+            }
+        } else {
+            output += format!("length: {}, date range: <na> - <na>", self.0.len()).as_str();
+        }
+
+        write!(f, "{}", output)
+    }
+}
+
+impl<T: fmt::Display + Copy> fmt::Display for DaySummaryStats<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\ntemp: {}\nhumidity: {}\ndew_point: {}\nvpd: {}\ngdd: {}\n",
+            self.date,
+            self.stats(Metric::Temperature),
+            self.stats(Metric::Humidity),
+            self.stats(Metric::DewPoint),
+            self.stats(Metric::Vpd),
+            self.gdd,
+        )
+    }
+}
+
+impl<T: Copy + PartialEq> DaySummaries<T> {
+    // Assumes Records are pre-sorted in a chronologically ascending order.
+    pub fn add_record(&mut self, record: &SensorRecord<T>, config: &Config) {
+        match self.0.last_mut() {
+            Some(day_summary_stats) if day_summary_stats.date == record.timestamp => {
+                day_summary_stats.update(record, config.gdd_base());
+            },
+            _ => {
+                self.0.push(DaySummaryStats::from_record(record, config.gdd_base()));
+            }
+        }
+    }
+
+    // Returns the (date, value) series of a metric's daily aggregate, e.g. for plotting.
+    pub fn series(&self, metric: Metric, aggregate: Aggregate) -> Vec<(T, f32)> {
+        self.0.iter().map(|day_summary_stats| (day_summary_stats.date, day_summary_stats.value(metric, aggregate))).collect()
+    }
+
+    // The days on which the cumulative GDD goes down to below zero. The mean method doesn't floor
+    // day GDD, so a cold day lowers the running total, which is expected now and then; but
+    // accumulating less than no heat at all is physically wrong and usually means the base is
+    // misconfigured. The total starts over at a reset event (see `GDD_RESET_EVENT`), and neither
+    // the first day nor a reset day has a total before it to go down from.
+    pub fn gdd_decreases(&self) -> Vec<T> {
+        let mut decreases = Vec::new();
+        let mut total_gdd = None;
+        for day_summary_stats in &self.0 {
+            if day_summary_stats.resets_gdd() {
+                total_gdd = None;
+            }
+            if day_summary_stats.gdd.is_nan() {
+                continue;
+            }
+            let total = total_gdd.unwrap_or(0.0) + day_summary_stats.gdd;
+            if total_gdd.is_some() && day_summary_stats.gdd < 0.0 && total < 0.0 {
+                decreases.push(day_summary_stats.date);
+            }
+            total_gdd = Some(total);
+        }
+        decreases
+    }
+
+    // The mean and population standard deviation of a metric's daily means across the measured
+    // days, e.g. for z-scoring them. None when no day has a reading of it.
+    pub fn daily_mean_distribution(&self, metric: Metric) -> Option<(f32, f32)> {
+        let means: Vec<f32> = self.0.iter()
+            .filter(|day_summary_stats| !day_summary_stats.interpolated)
+            .map(|day_summary_stats| day_summary_stats.value(metric, Aggregate::Mean))
+            .filter(|mean| !mean.is_nan())
+            .collect();
+        let mean = safe_mean(means.iter().sum(), means.len())?;
+        let variance = safe_mean(means.iter().map(|value| (value - mean).powi(2)).sum(), means.len())?;
+        Some((mean, variance.sqrt()))
+    }
+
+    // Splits the days into runs of consecutive days whose built-in metrics (mean, max, and min)
+    // all differ from the run's first day by less than `tolerance`. Days with events, and
+    // interpolated days next to measured ones, always start a new run.
+    pub fn stable_runs(&self, tolerance: f32) -> Vec<Range<usize>> {
+        let is_stable = |first: &DaySummaryStats<T>, day: &DaySummaryStats<T>| {
+            day.events.is_empty() && first.events.is_empty() && day.interpolated == first.interpolated &&
+                Metric::ALL.iter().all(|&metric| [Aggregate::Mean, Aggregate::Max, Aggregate::Min].iter()
+                    .all(|&aggregate| (day.value(metric, aggregate) - first.value(metric, aggregate)).abs() < tolerance))
+        };
+
+        let mut runs: Vec<Range<usize>> = Vec::new();
+        for (index, day_summary_stats) in self.0.iter().enumerate() {
+            match runs.last_mut() {
+                Some(run) if is_stable(&self.0[run.start], day_summary_stats) => run.end = index + 1,
+                _ => runs.push(index..index + 1),
+            }
+        }
+
+        runs
+    }
+
+    // Whole-season stats for every metric: the mean over all readings, and the extremes found
+    // among the days' own max and min, dated by the first day to reach them. Interpolated days
+    // are left out, as are metrics without any readings.
+    pub fn season_stats(&self) -> Vec<SeasonStats<T>> {
+        Metric::ALL.iter().filter_map(|&metric| {
+            let days: Vec<&DaySummaryStats<T>> = self.0.iter()
+                .filter(|day_summary_stats| !day_summary_stats.interpolated && day_summary_stats.stats(metric).count > 0)
+                .collect();
+            let max_day = days.iter().copied().reduce(|max_day, day| if day.value(metric, Aggregate::Max) > max_day.value(metric, Aggregate::Max) { day } else { max_day })?;
+            let min_day = days.iter().copied().reduce(|min_day, day| if day.value(metric, Aggregate::Min) < min_day.value(metric, Aggregate::Min) { day } else { min_day })?;
+
+            let mut sum = KahanSum::default();
+            let mut count = 0;
+            for day_summary_stats in &days {
+                for entry in day_summary_stats.entries(metric) {
+                    sum.add(*entry);
+                }
+                count += day_summary_stats.entries(metric).len();
+            }
+
+            Some(SeasonStats {
+                metric,
+                mean: safe_mean(sum.value(), count)?,
+                max: max_day.value(metric, Aggregate::Max),
+                max_date: max_day.date,
+                min: min_day.value(metric, Aggregate::Min),
+                min_date: min_day.date,
+            })
+        }).collect()
+    }
+}
+
+impl<T: Copy + PartialOrd> DaySummaries<T> {
+    // Growing degree days accumulated over the days after `start` up to and including `end`,
+    // with each day's contribution floored at zero.
+    pub fn gdd_between(&self, start: T, end: T, base: f32) -> f32 {
+        self.0.iter()
+            .filter(|day_summary_stats| day_summary_stats.date > start && day_summary_stats.date <= end)
+            .map(|day_summary_stats| (day_summary_stats.stats(Metric::Temperature).mean - base).max(0.0))
+            .sum()
+    }
+
+    // Searches the candidate bases for the one under which the GDD accumulated between consecutive
+    // phenology events (e.g. successive leaf appearances) is most uniform, i.e. has the lowest
+    // coefficient of variation. Needs at least three events, as two intervals are the minimum
+    // for a meaningful spread.
+    pub fn fit_gdd_base(&self, event_dates: &[T], bases: impl Iterator<Item = f32>) -> Option<GddBaseFit> {
+        if event_dates.len() < 3 {
+            return None;
+        }
+
+        bases.filter_map(|base| {
+            let intervals: Vec<f32> = event_dates.windows(2).map(|pair| self.gdd_between(pair[0], pair[1], base)).collect();
+            let mean = safe_mean(intervals.iter().sum(), intervals.len())?;
+            if mean <= 0.0 {
+                return None;
+            }
+            let variance = safe_mean(intervals.iter().map(|gdd| (gdd - mean).powi(2)).sum(), intervals.len())?;
+
+            Some(GddBaseFit { base, cv: variance.sqrt() / mean })
+        }).min_by(|x, y| x.cv.partial_cmp(&y.cv).unwrap())
+    }
+}
+
+// The frost-bounded growing season of a calendar year. Frosts before July 1st are spring
+// frosts and frosts from July 1st on are fall frosts.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrostSeason {
+    pub year: i32,
+    pub last_spring_frost: Option<NaiveDate>,
+    pub first_fall_frost: Option<NaiveDate>,
+}
+
+#[cfg(feature = "chrono")]
+impl FrostSeason {
+    // Days strictly between the last spring frost and the first fall frost, when both occurred.
+    pub fn frost_free_days(&self) -> Option<i64> {
+        match (self.last_spring_frost, self.first_fall_frost) {
+            (Some(last_spring_frost), Some(first_fall_frost)) => Some((first_fall_frost - last_spring_frost).num_days() - 1),
+            _ => None,
+        }
+    }
+}
+
+// The multi-year "normal" for one day of the year: the mean of each built-in metric's daily
+// means across every year with that day, in `Metric::ALL` order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClimatologyDay {
+    pub day_of_year: u32,
+    pub means: Vec<f32>,
+    pub years: usize, // how many days (one per year) were averaged.
+}
+
+// The day of the year on a 366-day calendar, so the same date always lines up across years:
+// February 29th is day 60 and March 1st is day 61 even when the year isn't a leap year.
+#[cfg(feature = "chrono")]
+pub fn day_of_leap_year(date: NaiveDate) -> u32 {
+    let is_leap_year = NaiveDate::from_ymd_opt(date.year(), 2, 29).is_some();
+    if !is_leap_year && date.month() > 2 { date.ordinal() + 1 } else { date.ordinal() }
+}
+
+#[cfg(feature = "chrono")]
+impl DaySummaries<NaiveDate> {
+    // The daily climatology: for each day of the year in the data (1–366, see `day_of_leap_year`),
+    // the mean across years of each metric's daily mean. Interpolated days are left out, and a
+    // metric without readings on a day doesn't count towards that day's mean.
+    pub fn climatology(&self) -> Vec<ClimatologyDay> {
+        let mut days_by_day_of_year: BTreeMap<u32, Vec<&DaySummaryStats<NaiveDate>>> = BTreeMap::new();
+        for day_summary_stats in self.0.iter().filter(|day_summary_stats| !day_summary_stats.interpolated) {
+            days_by_day_of_year.entry(day_of_leap_year(day_summary_stats.date)).or_default().push(day_summary_stats);
+        }
+
+        days_by_day_of_year.into_iter().map(|(day_of_year, days)| {
+            let means = Metric::ALL.iter().map(|&metric| {
+                let values: Vec<f32> = days.iter().map(|day| day.value(metric, Aggregate::Mean)).filter(|value| !value.is_nan()).collect();
+                safe_mean(values.iter().sum(), values.len()).unwrap_or(f32::NAN)
+            }).collect();
+            ClimatologyDay { day_of_year, means, years: days.len() }
+        }).collect()
+    }
+
+    // Runs of days without any readings between the first and last day, as inclusive (first,
+    // last) ranges. Interpolated days had no readings, so they count as missing.
+    pub fn gaps(&self) -> Vec<(NaiveDate, NaiveDate)> {
+        let dates: Vec<NaiveDate> = self.0.iter()
+            .filter(|day_summary_stats| !day_summary_stats.interpolated)
+            .map(|day_summary_stats| day_summary_stats.date)
+            .collect();
+        dates.windows(2)
+            .filter(|pair| (pair[1] - pair[0]).num_days() > 1)
+            .map(|pair| (pair[0].succ(), pair[1].pred()))
+            .collect()
+    }
+
+    // One frost season per calendar year in the data, based on days whose minimum temperature
+    // reached the freeze threshold. Years without frost have neither date set.
+    pub fn frost_seasons(&self, freeze_threshold: f32) -> Vec<FrostSeason> {
+        let mut frost_seasons: Vec<FrostSeason> = Vec::new();
+        for day_summary_stats in &self.0 {
+            let year = day_summary_stats.date.year();
+            if frost_seasons.last().is_none_or(|frost_season| frost_season.year != year) {
+                frost_seasons.push(FrostSeason { year, last_spring_frost: None, first_fall_frost: None });
+            }
+            let frost_season = frost_seasons.last_mut().unwrap();
+            if day_summary_stats.is_freeze_day(freeze_threshold) {
+                if day_summary_stats.date.month() < 7 {
+                    frost_season.last_spring_frost = Some(day_summary_stats.date);
+                } else if frost_season.first_fall_frost.is_none() {
+                    frost_season.first_fall_frost = Some(day_summary_stats.date);
+                }
+            }
+        }
+
+        frost_seasons
+    }
+
+    // Fills runs of fully missing days between two present days by linear interpolation, as long
+    // as a run is no longer than `max_span` days. Returns the number of days interpolated.
+    pub fn interpolate_missing_days(&mut self, max_span: i64, gdd_base: f32) -> usize {
+        let mut interpolated_days = 0;
+        let mut day_summaries: Vec<DaySummaryStats<NaiveDate>> = Vec::with_capacity(self.0.len());
+        for day_summary_stats in self.0.drain(..) {
+            let mut missing_days = Vec::new();
+            if let Some(before) = day_summaries.last() {
+                let span = (day_summary_stats.date - before.date).num_days();
+                if span > 1 && span - 1 <= max_span {
+                    for offset in 1..span {
+                        let date = before.date + DateDuration::days(offset);
+                        missing_days.push(DaySummaryStats::interpolate(date, before, &day_summary_stats, offset as f32 / span as f32, gdd_base));
+                    }
+                }
+            }
+            interpolated_days += missing_days.len();
+            day_summaries.extend(missing_days);
+            day_summaries.push(day_summary_stats);
+        }
+        self.0 = day_summaries;
+
+        interpolated_days
+    }
+
+    // A copy of the series with a day for every date from the first to the last, the missing ones
+    // filled per `fill` and marked as interpolated. Filled days carry no readings or events.
+    pub fn resample_daily(&self, fill: FillPolicy) -> DaySummaries<NaiveDate> {
+        let mut day_summaries: Vec<DaySummaryStats<NaiveDate>> = Vec::with_capacity(self.0.len());
+        for (index, day_summary_stats) in self.0.iter().enumerate() {
+            if let Some(before) = index.checked_sub(1).map(|before| &self.0[before]) {
+                let span = (day_summary_stats.date - before.date).num_days();
+                for offset in 1..span {
+                    // Interpolating at a NaN fraction leaves every stat missing, and from the day
+                    // before to itself repeats it.
+                    let (after, fraction) = match fill {
+                        FillPolicy::None => (day_summary_stats, f32::NAN),
+                        FillPolicy::Forward => (before, 0.0),
+                        FillPolicy::Interpolate => (day_summary_stats, offset as f32 / span as f32),
+                    };
+                    let mut missing_day = DaySummaryStats::interpolate(before.date + DateDuration::days(offset), before, after, fraction, 0.0);
+                    missing_day.gdd = before.gdd + (after.gdd - before.gdd) * fraction;
+                    day_summaries.push(missing_day);
+                }
+            }
+            day_summaries.push(day_summary_stats.clone());
+        }
+
+        DaySummaries(day_summaries)
+    }
+
+    // Groups records into buckets of the configured granularity, labelled by each bucket's start
+    // date. Records are placed by their agronomic day (see `Calendar::agronomic_date`).
+    pub fn add_record_grouped(&mut self, record: &SensorRecord<NaiveDate>, config: &Config) {
+        let granularity = config.granularity;
+        let date = config.calendar.agronomic_date(record.timestamp, record.seconds_of_day);
+        match self.0.last_mut() {
+            Some(bucket_stats) if granularity.bucket_key(bucket_stats.date, &config.calendar) == granularity.bucket_key(date, &config.calendar) => {
+                bucket_stats.update(record, config.gdd_base());
+            },
+            _ => {
+                let mut bucket_stats = DaySummaryStats::from_record(record, config.gdd_base());
+                bucket_stats.date = granularity.bucket_start(date, &config.calendar);
+                if config.approx_percentiles {
+                    if let Some(percentile) = config.percentile {
+                        bucket_stats.estimate_percentiles(percentile as f64 / 100.0);
+                    }
+                    bucket_stats.estimate_medians();
+                }
+                self.0.push(bucket_stats);
+            }
+        }
+    }
+
+    // The start of the current (latest) bucket when the record falls before it. Buckets are
+    // completed as soon as a later one starts, so such a record can't be folded in anymore.
+    pub fn out_of_order(&self, record: &SensorRecord<NaiveDate>, config: &Config) -> Option<NaiveDate> {
+        let date = config.granularity.bucket_start(config.calendar.agronomic_date(record.timestamp, record.seconds_of_day), &config.calendar);
+        self.0.last().map(|bucket_stats| bucket_stats.date).filter(|current| date < *current)
+    }
+}
+
+// Every analysis of a run's day summaries in one place, as requested by the config: sections that
+// weren't asked for are None. Gaps only apply to daily summaries and are empty otherwise.
+#[cfg(feature = "chrono")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub days: usize,
+    pub gaps: Vec<(NaiveDate, NaiveDate)>,
+    pub gdd_decreases: Vec<NaiveDate>,
+    pub frost_seasons: Option<Vec<FrostSeason>>,
+    pub season_stats: Option<Vec<SeasonStats<NaiveDate>>>,
+    pub climatology: Option<Vec<ClimatologyDay>>,
+    pub gdd_base_fit: Option<GddBaseFit>, // None when not requested, or when there were too few events to fit.
+    pub tally: Option<RowTally>, // the rows of the input a run read and skipped; None for a report that wasn't made from reading one.
+}
+
+// The report for a dataset of readings, which are summarized into days (or buckets) first. The
+// dataset carries no events, so there's no gdd base fit.
+#[cfg(feature = "chrono")]
+pub fn analyze(dataset: &SensorDataset<NaiveDate>, config: &Config) -> Report {
+    let mut day_summaries = DaySummaries(Vec::new());
+    for record in &dataset.0 {
+        day_summaries.add_record_grouped(record, config);
+    }
+    for day_summary_stats in &mut day_summaries.0 {
+        day_summary_stats.apply_gdd_method(config.gdd_method, config.gdd_base(), config.degree_day_caps());
+    }
+    analyze_summaries(&day_summaries, config)
+}
+
+// The report for a run's day summaries, e.g. those `process` returns.
+#[cfg(feature = "chrono")]
+pub fn analyze_summaries(day_summaries: &DaySummaries<NaiveDate>, config: &Config) -> Report {
+    Report {
+        days: day_summaries.0.len(),
+        gaps: if config.granularity == Granularity::Day { day_summaries.gaps() } else { Vec::new() },
+        gdd_decreases: day_summaries.gdd_decreases(),
+        frost_seasons: config.frost_dates.then(|| day_summaries.frost_seasons(config.freeze_threshold())),
+        season_stats: config.season_report.then(|| day_summaries.season_stats()),
+        climatology: config.climatology.as_ref().map(|_| day_summaries.climatology()),
+        gdd_base_fit: config.fit_gdd_base.and_then(|base_range| {
+            // One date per event, so that events sharing a day still count as separate intervals.
+            let event_dates: Vec<NaiveDate> = day_summaries.iter()
+                .flat_map(|day_summary| day_summary.events.iter().map(move |_| day_summary.date))
+                .collect();
+            day_summaries.fit_gdd_base(&event_dates, base_range.bases())
+        }),
+        tally: None,
+    }
+}
+
+// JSON values for `Report::to_json`: numbers that aren't finite and missing dates are null.
+#[cfg(feature = "chrono")]
+fn json_number(value: f32) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+#[cfg(feature = "chrono")]
+fn json_date(date: Option<NaiveDate>) -> String {
+    date.map_or("null".to_string(), |date| format!("\"{}\"", date))
+}
+
+#[cfg(feature = "chrono")]
+fn json_array<T>(items: &[T], item: impl Fn(&T) -> String) -> String {
+    format!("[{}]", items.iter().map(item).collect::<Vec<String>>().join(","))
+}
+
+#[cfg(feature = "chrono")]
+impl Report {
+    // The report as a single JSON object keyed by section, with unrequested sections null.
+    pub fn to_json(&self) -> String {
+        let frost_seasons = self.frost_seasons.as_ref().map_or("null".to_string(), |frost_seasons| json_array(frost_seasons, |frost_season| format!(
+            "{{\"year\":{},\"last_spring_frost\":{},\"first_fall_frost\":{},\"frost_free_days\":{}}}",
+            frost_season.year,
+            json_date(frost_season.last_spring_frost),
+            json_date(frost_season.first_fall_frost),
+            frost_season.frost_free_days().map_or("null".to_string(), |days| days.to_string()),
+        )));
+        let season_stats = self.season_stats.as_ref().map_or("null".to_string(), |season_stats| json_array(season_stats, |season_stats| format!(
+            "{{\"metric\":\"{}\",\"mean\":{},\"max\":{},\"max_date\":{},\"min\":{},\"min_date\":{}}}",
+            season_stats.metric,
+            json_number(season_stats.mean),
+            json_number(season_stats.max),
+            json_date(Some(season_stats.max_date)),
+            json_number(season_stats.min),
+            json_date(Some(season_stats.min_date)),
+        )));
+        let climatology = self.climatology.as_ref().map_or("null".to_string(), |climatology| json_array(climatology, |climatology_day| format!(
+            "{{\"day_of_year\":{},\"means\":{{{}}},\"years\":{}}}",
+            climatology_day.day_of_year,
+            Metric::ALL.iter().zip(&climatology_day.means).map(|(metric, mean)| format!("\"{}\":{}", metric, json_number(*mean))).collect::<Vec<String>>().join(","),
+            climatology_day.years,
+        )));
+        let gdd_base_fit = self.gdd_base_fit.map_or("null".to_string(), |fit| format!("{{\"base\":{},\"cv\":{}}}", json_number(fit.base), json_number(fit.cv)));
+
+        format!(
+            "{{\"days\":{},\"gaps\":{},\"gdd_decreases\":{},\"frost_seasons\":{},\"season_stats\":{},\"climatology\":{},\"gdd_base_fit\":{},\"tally\":{}}}",
+            self.days,
+            json_array(&self.gaps, |(first, last)| format!("{{\"first\":{},\"last\":{}}}", json_date(Some(*first)), json_date(Some(*last)))),
+            json_array(&self.gdd_decreases, |date| json_date(Some(*date))),
+            frost_seasons,
+            season_stats,
+            climatology,
+            gdd_base_fit,
+            self.tally.map_or("null".to_string(), |tally| tally.to_json()),
+        )
+    }
+}
+
+// Reads dated notes, such as the events file or annotations, from csv rows of a timestamp and a
+// note. Returns them sorted by date, keeping the listed order within a day.
+#[cfg(feature = "chrono")]
+pub fn read_events(input: impl Read) -> Result<Vec<(NaiveDate, String)>, SensoryError> {
+    read_events_with_format(input, None)
+}
+
+// Like `read_events`, with timestamps in the given `date_format` (see `parse_timestamp`).
+#[cfg(feature = "chrono")]
+pub fn read_events_with_format(input: impl Read, date_format: Option<&str>) -> Result<Vec<(NaiveDate, String)>, SensoryError> {
+    let mut events = Vec::new();
+    for record in csv::Reader::from_reader(input).records() {
+        let record = strip_carriage_returns(record?);
+        let date = parse_timestamp(&record[0], date_format).map(|(date, _)| date).ok_or_else(|| SensoryError::Events(RowError {
+            line: record.position().map_or(0, |position| position.line()),
+            message: format!("invalid date: {:?}", &record[0]),
+        }))?;
+        events.push((date, record.get(1).unwrap_or("").to_string()));
+    }
+    events.sort_by_key(|(date, _)| *date);
+
+    Ok(events)
+}
+
+// A sign of a misbehaving sensor clock: a reading timestamped before the one just before it, or
+// with the very same timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClockIssue {
+    Backwards { line: u64, previous: String, timestamp: String },
+    Stalled { line: u64, timestamp: String },
+}
+
+impl fmt::Display for ClockIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClockIssue::Backwards { line, previous, timestamp } => write!(f, "line {}: clock went back from {} to {}", line, previous, timestamp),
+            ClockIssue::Stalled { line, timestamp } => write!(f, "line {}: clock stalled at {}", line, timestamp),
+        }
+    }
+}
+
+// Scans sensor data in its original order for backwards jumps and stalled timestamps, without
+// changing anything about how it's summarized. Rows whose timestamp doesn't parse are passed over.
+#[cfg(feature = "chrono")]
+pub fn clock_issues(input: impl Read, config: &Config) -> Result<Vec<ClockIssue>, SensoryError> {
+    let mut issues = Vec::new();
+    let mut previous: Option<((NaiveDate, u32), String)> = None;
+    for record in csv::ReaderBuilder::new().flexible(true).from_reader(input).records().skip(config.units_row as usize) {
+        let record = record?;
+        let timestamp = record.get(0).unwrap_or("");
+        let time = match parse_timestamp(timestamp, config.date_format.as_deref()) {
+            Some(time) => time,
+            None => continue,
+        };
+        let line = record.position().map_or(0, |position| position.line());
+        match &previous {
+            Some((previous_time, previous_timestamp)) if time < *previous_time => {
+                issues.push(ClockIssue::Backwards { line, previous: previous_timestamp.clone(), timestamp: timestamp.to_string() });
+            },
+            Some((previous_time, _)) if time == *previous_time => issues.push(ClockIssue::Stalled { line, timestamp: timestamp.to_string() }),
+            _ => {},
+        }
+        previous = Some((time, timestamp.to_string()));
+    }
+    Ok(issues)
+}
+
+// One metric's values on one date of two summary outputs. A date missing from either summary
+// leaves that side empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SummaryDiff {
+    pub date: String,
+    pub metric: String,
+    pub value: Option<f32>,
+    pub other: Option<f32>,
+}
+
+impl SummaryDiff {
+    // How much the other summary's value differs from this one's.
+    pub fn difference(&self) -> Option<f32> {
+        Some(self.other? - self.value?)
+    }
+
+    // The difference as a percentage of this summary's value, or None when that is zero.
+    pub fn percent_difference(&self) -> Option<f32> {
+        let value = self.value?;
+        if value == 0.0 {
+            return None;
+        }
+        Some(self.difference()? / value.abs() * 100.0)
+    }
+}
+
+// Diffs two summary outputs (as written by this tool, with a date column first) date by date,
+// for every numeric column the two have in common. Dates are listed in order, including those
+// that appear in only one summary.
+pub fn compare_summaries(summary: impl Read, other: impl Read) -> Result<Vec<SummaryDiff>, SensoryError> {
+    fn read_summary(input: impl Read) -> Result<(Vec<String>, BTreeMap<String, csv::StringRecord>), csv::Error> {
+        let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).flexible(true).from_reader(input);
+        let header = reader.headers()?.iter().map(|name| name.to_string()).collect();
+        let mut rows = BTreeMap::new();
+        for record in reader.records() {
+            let record = record?;
+            if let Some(date) = record.get(0).map(str::to_string) {
+                rows.insert(date, record);
+            }
+        }
+        Ok((header, rows))
+    }
+
+    let (header, rows) = read_summary(summary)?;
+    let (other_header, other_rows) = read_summary(other)?;
+    let columns: Vec<(usize, usize)> = header.iter().enumerate().skip(1)
+        .filter_map(|(index, name)| other_header.iter().position(|other_name| other_name == name).map(|other_index| (index, other_index)))
+        .collect();
+    let parse = |row: Option<&csv::StringRecord>, index: usize| row.and_then(|row| row.get(index)).and_then(|field| field.parse::<f32>().ok());
+
+    let mut dates: Vec<&String> = rows.keys().chain(other_rows.keys()).collect();
+    dates.sort();
+    dates.dedup();
+    let mut diffs = Vec::new();
+    for date in dates {
+        let (row, other_row) = (rows.get(date), other_rows.get(date));
+        for &(index, other_index) in &columns {
+            let (value, other) = (parse(row, index), parse(other_row, other_index));
+            // Columns that aren't numbers (events, flags) are skipped, unless the date is only in
+            // one summary and the column is numeric there.
+            let numeric = match (row, other_row) {
+                (Some(_), Some(_)) => value.is_some() && other.is_some(),
+                _ => value.is_some() || other.is_some(),
+            };
+            if numeric {
+                diffs.push(SummaryDiff { date: date.clone(), metric: header[index].clone(), value, other });
+            }
+        }
+    }
+
+    Ok(diffs)
+}
+
+// Hooks into `process_with` for callers that act on the data while it is read, e.g. to stream
+// output or keep copies of the raw rows. Every hook does nothing by default.
+#[cfg(feature = "chrono")]
+pub trait ProcessObserver {
+    // Called with the input's header row before any records.
+    fn on_headers(&mut self, _headers: &csv::StringRecord) -> Result<(), SensoryError> {
+        Ok(())
+    }
+
+    // Called for each parsed record after unit conversion, including future-dated records that
+    // `drop_future` then leaves out of the summaries.
+    fn on_record(&mut self, _raw: &csv::StringRecord, _record: &SensorRecord<NaiveDate>) -> Result<(), SensoryError> {
+        Ok(())
+    }
+
+    // Called for each row skipped under `report_errors`.
+    fn on_reject(&mut self, _raw: &csv::StringRecord, _err: &RowError) -> Result<(), SensoryError> {
+        Ok(())
+    }
+
+    // Called once a day (or bucket) has received all of its readings, in chronological order.
+    // Interpolated days are not reported.
+    fn on_day_complete(&mut self, _day_summary: &DaySummaryStats<NaiveDate>) -> Result<(), SensoryError> {
+        Ok(())
+    }
+
+    // Called with each warning about the data once the read has finished.
+    fn on_warning(&mut self, _warning: &str) {}
+
+    // Called once the read has finished with how many rows were summarized and skipped.
+    fn on_tally(&mut self, _tally: &RowTally) {}
+
+    // Polled before each record; once it returns true the read stops early. The days completed
+    // so far are still summarized, but the day in progress is left out.
+    fn interrupted(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ProcessObserver for () {}
+
+// Averages readings over fixed slots of each day, e.g. hours, before they're summarized, so that
+// a slot sampled more densely than the rest doesn't outweigh them in the day's stats. Readings
+// must arrive in chronological order; each slot's average is dated at the slot's start.
+#[cfg(feature = "chrono")]
+struct PreAggregator {
+    interval_seconds: u32,
+    pending: Vec<SensorRecord<NaiveDate>>,
+}
+
+#[cfg(feature = "chrono")]
+impl PreAggregator {
+    fn slot(&self, record: &SensorRecord<NaiveDate>) -> (NaiveDate, u32) {
+        (record.timestamp, record.seconds_of_day / self.interval_seconds)
+    }
+
+    // Adds a reading, returning the previous slot's average once a reading for a new slot arrives.
+    fn push(&mut self, record: SensorRecord<NaiveDate>) -> Option<SensorRecord<NaiveDate>> {
+        let finished = match self.pending.first() {
+            Some(first) if self.slot(first) != self.slot(&record) => self.finish(),
+            _ => None,
+        };
+        self.pending.push(record);
+        finished
+    }
+
+    // The average of the pending slot, if it has any readings. Missing (NaN) values are left out
+    // of each mean; the flag is set when any reading's was.
+    fn finish(&mut self) -> Option<SensorRecord<NaiveDate>> {
+        let first = self.pending.first()?;
+        let pending = &self.pending;
+        let mean = |reading: &dyn Fn(&SensorRecord<NaiveDate>) -> f32| {
+            let values: Vec<f32> = pending.iter().map(reading).filter(|value| !value.is_nan()).collect();
+            safe_mean(values.iter().sum(), values.len()).unwrap_or(f32::NAN)
+        };
+        let record = SensorRecord {
+            timestamp: first.timestamp,
+            seconds_of_day: self.slot(first).1 * self.interval_seconds,
+            temperature: mean(&|record| record.temperature),
+            humidity: mean(&|record| record.humidity),
+            dew_point: mean(&|record| record.dew_point),
+            vpd: mean(&|record| record.vpd),
+            custom: (0..first.custom.len()).map(|index| mean(&|record| record.custom[index])).collect(),
+            apparent_temperature: mean(&|record| record.apparent_temperature),
+            flag: pending.iter().any(|record| record.flag),
+        };
+        self.pending.clear();
+        Some(record)
+    }
+}
+
+// Reads sensor data and optional events csv input and summarizes it as configured, without
+// writing any output. Inputs with a units row need it applied to the config beforehand (see
+// `read_units_row`); the row itself is skipped here.
+#[cfg(feature = "chrono")]
+pub fn process(config: &Config, input: impl Read, events: Option<impl Read>) -> Result<DaySummaries<NaiveDate>, SensoryError> {
+    process_with(config, input, events, &mut ())
+}
+
+#[cfg(feature = "chrono")]
+pub fn process_with(config: &Config, input: impl Read, events: Option<impl Read>, observer: &mut impl ProcessObserver) -> Result<DaySummaries<NaiveDate>, SensoryError> {
+    process_group(config, input, events, observer, None)
+}
+
+// The second pass of `exact_medians`: reads the input again, keeping each day's readings only
+// until the day is complete, and replaces the estimated medians (and the requested `percentile`)
+// in `day_summaries` (from the first pass over the same input and `label`) with the exact ones.
+#[cfg(feature = "chrono")]
+pub fn apply_exact_medians(config: &Config, input: impl Read, day_summaries: &mut DaySummaries<NaiveDate>, label: Option<&str>) -> Result<(), SensoryError> {
+    // Each day's (median, percentile) for every metric.
+    struct Medians<'a>(&'a Config, HashMap<NaiveDate, Vec<(f32, Option<f32>)>>);
+
+    impl ProcessObserver for Medians<'_> {
+        fn on_day_complete(&mut self, day_summary: &DaySummaryStats<NaiveDate>) -> Result<(), SensoryError> {
+            let config = self.0;
+            let exact = day_summary.metric_stats.iter()
+                .map(|metric_stats| (metric_stats.median(), config.percentile.map(|percentile| metric_stats.percentile(percentile as f64 / 100.0, config.percentile_method))))
+                .collect();
+            self.1.insert(day_summary.date, exact);
+            Ok(())
+        }
+    }
+
+    let exact_config = Config { approx_percentiles: false, exact_medians: true, ..config.clone() };
+    let mut medians = Medians(config, HashMap::new());
+    process_group(&exact_config, input, None::<&[u8]>, &mut medians, label)?;
+    for day_summary_stats in &mut day_summaries.0 {
+        if let Some(exact) = medians.1.get(&day_summary_stats.date) {
+            for (metric_stats, (median, percentile)) in day_summary_stats.metric_stats.iter_mut().zip(exact) {
+                metric_stats.median = *median;
+                if let (Some(fraction), Some(value)) = (config.percentile, percentile) {
+                    metric_stats.fix_percentile(fraction as f64 / 100.0, *value);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Whether a boolean column's value is true: `1`, `true`, `yes` and their abbreviations, in any
+// case. Anything else, including an empty value, is false.
+pub fn is_truthy(value: &str) -> bool {
+    ["1", "true", "t", "yes", "y"].iter().any(|truthy| truthy.eq_ignore_ascii_case(value.trim()))
+}
+
+// The index of the column named `name`, ignoring case and surrounding whitespace.
+pub fn column_index(header: &csv::StringRecord, name: &str) -> Option<usize> {
+    header.iter().position(|column| column.trim().eq_ignore_ascii_case(name.trim()))
+}
+
+// The distinct labels in the `--group-by` column, in the order they first appear. Rows without
+// the column share the empty label.
+pub fn group_labels(input: impl Read, config: &Config) -> Result<Vec<String>, SensoryError> {
+    let column = config.group_by.as_deref().unwrap_or_default();
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(input);
+    let index = column_index(reader.headers()?, column).ok_or_else(|| SensoryError::Schema(format!("No column named {:?} to group by", column)))?;
+    let mut records = reader.records();
+    if config.units_row {
+        records.next();
+    }
+
+    let mut labels: Vec<String> = Vec::new();
+    for record in records {
+        let label = strip_carriage_returns(record?).get(index).unwrap_or("").to_string();
+        if !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    Ok(labels)
+}
+
+// Like `process_with`, but with a `label` only the rows whose `--group-by` column holds it are
+// summarized; the rest are skipped before parsing, so they are never rejected or observed.
+#[cfg(feature = "chrono")]
+pub fn process_group(config: &Config, input: impl Read, events: Option<impl Read>, observer: &mut impl ProcessObserver, label: Option<&str>) -> Result<DaySummaries<NaiveDate>, SensoryError> {
+    fn attach_events(day_summary_stats: &mut DaySummaryStats<NaiveDate>, events_by_bucket: &mut HashMap<BucketKey, Vec<String>>, config: &Config) {
+        if let Some(events) = events_by_bucket.remove(&config.granularity.bucket_key(day_summary_stats.date, &config.calendar)) {
+            day_summary_stats.events.extend(events);
+        }
+    }
+
+    // Records a (bucket, seconds, value) reading and, when the previous reading with a value fell
+    // in the same bucket, returns the hours since it along with its value. NaN values are skipped.
+    fn integration_step(previous: &mut Option<(usize, i64, f32)>, bucket: usize, seconds: i64, value: f32) -> Option<(f32, f32)> {
+        if value.is_nan() {
+            return None;
+        }
+        let step = previous
+            .filter(|(previous_bucket, _, _)| *previous_bucket == bucket)
+            .map(|(_, previous_seconds, previous_value)| ((seconds - previous_seconds) as f32 / 3600.0, previous_value));
+        *previous = Some((bucket, seconds, value));
+        step
+    }
+
+    // The summaries being built, with what's needed to integrate readings into them and to
+    // complete each day in turn.
+    struct Rollup {
+        day_summaries: DaySummaries<NaiveDate>,
+        completed_days: usize,
+        previous_vpd_reading: Option<(usize, i64, f32)>,
+        previous_temperature_reading: Option<(usize, i64, f32)>,
+    }
+
+    impl Rollup {
+        fn add(&mut self, record_entry: &SensorRecord<NaiveDate>, config: &Config, events_by_bucket: &mut HashMap<BucketKey, Vec<String>>, observer: &mut impl ProcessObserver) -> Result<(), SensoryError> {
+            let day_summaries = &mut self.day_summaries;
+            day_summaries.add_record_grouped(record_entry, config);
+            // Integrate vpd and the degrees above the gdd base over each pair of consecutive readings
+            // (with a value) within a bucket; the span between buckets belongs to neither.
+            let bucket = day_summaries.0.len() - 1;
+            if record_entry.at_rail(&config.rails) {
+                day_summaries.0[bucket].rail_readings += 1;
+            }
+            let reading_seconds = record_entry.timestamp.num_days_from_ce() as i64 * SECONDS_PER_DAY as i64 + record_entry.seconds_of_day as i64;
+            if let Some((hours, previous_vpd)) = integration_step(&mut self.previous_vpd_reading, bucket, reading_seconds, record_entry.vpd) {
+                day_summaries.0[bucket].vpd_hours += (previous_vpd + record_entry.vpd) / 2.0 * hours;
+            }
+            if let Some((hours, previous_temperature)) = integration_step(&mut self.previous_temperature_reading, bucket, reading_seconds, record_entry.temperature) {
+                let day_summary_stats = &mut day_summaries.0[bucket];
+                let bases = config.gdd_bases();
+                day_summary_stats.degree_hours.resize(bases.len(), 0.0);
+                for (degree_hours, base) in day_summary_stats.degree_hours.iter_mut().zip(bases) {
+                    *degree_hours += ((previous_temperature - base).max(0.0) + (record_entry.temperature - base).max(0.0)) / 2.0 * hours;
+                }
+                day_summary_stats.degree_hours_span += hours;
+            }
+
+            // A day is complete once a reading for a later day arrives.
+            while self.completed_days + 1 < day_summaries.0.len() {
+                let day_summary_stats = &mut day_summaries.0[self.completed_days];
+                day_summary_stats.apply_gdd_method(config.gdd_method, config.gdd_base(), config.degree_day_caps());
+                attach_events(day_summary_stats, events_by_bucket, config);
+                observer.on_day_complete(day_summary_stats)?;
+                if config.exact_medians {
+                    day_summary_stats.release_entries();
+                }
+                self.completed_days += 1;
+            }
+            Ok(())
+        }
+    }
+
+    let mut events_by_bucket: HashMap<BucketKey, Vec<String>> = HashMap::new();
+    if let Some(events) = events {
+        for (date, event) in read_events_with_format(events, config.event_date_format())? {
+            events_by_bucket.entry(config.granularity.bucket_key(date, &config.calendar)).or_default().push(event);
+        }
+    }
+
+    // The input is read a record at a time, each checked to be UTF-8 as it's read, so only the
+    // days not yet complete are held in memory. When reporting errors, rows with the wrong number
+    // of fields are reported rather than aborting the read.
+    let mut sensor_reader = csv::ReaderBuilder::new().flexible(config.report_errors).from_reader(input);
+    let mut truncated = false;
+    if config.schema_check {
+        check_schema(sensor_reader.headers()?, &config.expected_columns())?;
+    }
+    observer.on_headers(sensor_reader.headers()?)?;
+    let wind_column = wind_speed_column(sensor_reader.headers()?);
+    // Basic sensors log only temperature and humidity, perhaps with a dew point; the dew point
+    // and vpd are derived from those two when the input has no column for them.
+    let dew_point_column = dew_point_column(sensor_reader.headers()?);
+    let vpd_column = vpd_column(sensor_reader.headers()?);
+    let flag_column = match &config.flag_column {
+        Some(column) => Some(column_index(sensor_reader.headers()?, column).ok_or_else(|| SensoryError::Schema(format!("No column named {:?} to flag days by", column)))?),
+        None => None,
+    };
+    let quality_column = match &config.quality_column {
+        Some(column) => Some(column_index(sensor_reader.headers()?, column).ok_or_else(|| SensoryError::Schema(format!("No column named {:?} to read quality flags from", column)))?),
+        None => None,
+    };
+    let good_quality = config.good_quality();
+    let group_column = match (&config.group_by, label) {
+        (Some(column), Some(_)) => Some(column_index(sensor_reader.headers()?, column).ok_or_else(|| SensoryError::Schema(format!("No column named {:?} to group by", column)))?),
+        _ => None,
+    };
+
+    let sensor_records = sensor_reader.records().skip(config.units_row as usize).map(|record| record.map(strip_carriage_returns));
+    // Days are completed as soon as a reading for a later day arrives, so the rows are streamed
+    // in the order they come and a reading for an already completed day is an error. Sorting
+    // means holding every row in memory, so it's only done when asked for. Rows whose timestamp
+    // doesn't parse sort first, and a possibly truncated final record stays last.
+    let sensor_records: Box<dyn Iterator<Item = csv::Result<csv::StringRecord>>> = if config.sort_input {
+        let mut sensor_records: Vec<csv::Result<csv::StringRecord>> = sensor_records.collect();
+        let sortable = sensor_records.len().saturating_sub(config.tolerate_truncation as usize);
+        sensor_records[..sortable].sort_by_key(|record| parse_timestamp(record.as_ref().ok()?.get(0)?, config.date_format.as_deref()));
+        Box::new(sensor_records.into_iter())
+    } else {
+        Box::new(sensor_records)
+    };
+    let mut sensor_records = sensor_records.peekable();
+
+    let parse_options = ParseOptions { na_values: config.na_values(), date_format: config.date_format.clone(), dew_point_column, vpd_column };
+    let na_values = &parse_options.na_values;
+    let max_date = config.max_date();
+    let mut future_records = 0;
+    let mut clamped_vpd_records = 0;
+    let mut tally = RowTally::default();
+    let mut valid_records = 0;
+    let mut pre_aggregator = config.pre_aggregate.map(|interval| PreAggregator { interval_seconds: interval.0.as_secs() as u32, pending: Vec::new() });
+    let mut rollup = Rollup { day_summaries: DaySummaries(Vec::new()), completed_days: 0, previous_vpd_reading: None, previous_temperature_reading: None };
+    let mut interrupted = false;
+    while let Some(record) = sensor_records.next() {
+        if observer.interrupted() {
+            interrupted = true;
+            break;
+        }
+        // A file cut off mid-write ends in an incomplete record; when tolerating truncation a
+        // failure on the final record ends the read instead of discarding everything before it.
+        let at_final_record = config.tolerate_truncation && sensor_records.peek().is_none();
+        let record: csv::StringRecord = match record {
+            Ok(record) => record,
+            Err(_) if at_final_record => {
+                truncated = true;
+                tally.parse_errors += 1;
+                break;
+            },
+            Err(err) => return Err(err.into()),
+        };
+        if group_column.is_some_and(|column| Some(record.get(column).unwrap_or("")) != label) {
+            continue;
+        }
+        // Bad readings are often junk, so they're left out before they're parsed.
+        if let Some(column) = quality_column {
+            let flag = record.get(column).unwrap_or("").trim();
+            if !good_quality.iter().any(|good| good.eq_ignore_ascii_case(flag)) {
+                tally.flagged_bad += 1;
+                continue;
+            }
+        }
+        let mut record_entry = match SensorRecord::parse_csv_record(&record, &parse_options) {
+            Ok(record_entry) => record_entry,
+            Err(_) if at_final_record => {
+                truncated = true;
+                tally.parse_errors += 1;
+                break;
+            },
+            Err(err) if config.report_errors => {
+                observer.on_reject(&record, &err)?;
+                tally.parse_errors += 1;
+                continue;
+            },
+            Err(err) => return Err(SensoryError::Parse(err)),
+        };
+        for (metric, calibration) in &config.calibrations {
+            let reading = metric.reading_mut(&mut record_entry);
+            *reading = calibration.apply(*reading);
+        }
+        record_entry.humidity = config.humidity_scale.to_percent(record_entry.humidity);
+        if let Err(err) = record_entry.check_bounds(config, record.position().map_or(0, |position| position.line())) {
+            if let (true, SensoryError::Parse(row_error)) = (config.report_errors, &err) {
+                observer.on_reject(&record, row_error)?;
+                tally.out_of_range += 1;
+                continue;
+            }
+            return Err(err);
+        }
+        if let Some(current) = rollup.day_summaries.out_of_order(&record_entry, config) {
+            let err = RowError {
+                line: record.position().map_or(0, |position| position.line()),
+                message: format!("out of order: {} is before {}, which is already being summarized; sort the input by timestamp or pass --sort-input", &record[0], current),
+            };
+            if config.report_errors {
+                observer.on_reject(&record, &err)?;
+                tally.out_of_order += 1;
+                continue;
+            }
+            return Err(SensoryError::Parse(err));
+        }
+        // Resuming picks up with the first day (or bucket) after the checkpoint's.
+        if config.resume_after.is_some_and(|date| config.granularity.bucket_start(config.calendar.agronomic_date(record_entry.timestamp, record_entry.seconds_of_day), &config.calendar) <= date) {
+            continue;
+        }
+        valid_records += 1;
+        if config.sample_every.is_some_and(|sample_every| (valid_records - 1) % sample_every != 0) {
+            tally.sampled_out += 1;
+            continue;
+        }
+        if config.between_hours.is_some_and(|window| !window.contains(record_entry.seconds_of_day)) {
+            tally.outside_hours += 1;
+            continue;
+        }
+        if !config.metrics.is_empty() {
+            let row: csv::StringRecord = record.iter()
+                .map(|field| if na_values.iter().any(|na_value| na_value.eq_ignore_ascii_case(field.trim())) { "" } else { field })
+                .collect();
+            record_entry.custom = config.metrics.iter().map(|metric| metric.extract(&row).unwrap_or(f32::NAN)).collect();
+        }
+        if let Some(wind_speed) = wind_column.and_then(|column| record.get(column)) {
+            let wind_speed = if na_values.iter().any(|na_value| na_value.eq_ignore_ascii_case(wind_speed.trim())) { f32::NAN } else { wind_speed.trim().parse().unwrap_or(f32::NAN) };
+            record_entry.apparent_temperature = apparent_temperature(record_entry.temperature, record_entry.humidity, wind_speed, config.scale);
+        }
+        if let Some(column) = flag_column {
+            record_entry.flag = is_truthy(record.get(column).unwrap_or(""));
+        }
+        if dew_point_column.is_none() {
+            record_entry.derive_dew_point(config.scale);
+        }
+        if (config.derive_vpd || vpd_column.is_none()) && record_entry.derive_vpd(config.scale) {
+            clamped_vpd_records += 1;
+        }
+        observer.on_record(&record, &record_entry)?;
+        if record_entry.is_future(max_date) {
+            future_records += 1;
+            if config.drop_future {
+                tally.dropped_future += 1;
+                continue;
+            }
+        }
+        tally.processed += 1;
+        match pre_aggregator.as_mut() {
+            Some(pre_aggregator) => {
+                if let Some(slot_mean) = pre_aggregator.push(record_entry) {
+                    rollup.add(&slot_mean, config, &mut events_by_bucket, observer)?;
+                }
+            },
+            None => rollup.add(&record_entry, config, &mut events_by_bucket, observer)?,
+        }
+    }
+    if let Some(slot_mean) = pre_aggregator.as_mut().filter(|_| !interrupted).and_then(PreAggregator::finish) {
+        rollup.add(&slot_mean, config, &mut events_by_bucket, observer)?;
+    }
+    let Rollup { mut day_summaries, completed_days, .. } = rollup;
+    if interrupted {
+        day_summaries.0.truncate(completed_days);
+    }
+    for day_summary_stats in &mut day_summaries.0[completed_days..] {
+        day_summary_stats.apply_gdd_method(config.gdd_method, config.gdd_base(), config.degree_day_caps());
+        attach_events(day_summary_stats, &mut events_by_bucket, config);
+        observer.on_day_complete(day_summary_stats)?;
+        if config.exact_medians {
+            day_summary_stats.release_entries();
+        }
+    }
+
+    if truncated {
+        observer.on_warning("the sensor data ended unexpectedly; the incomplete final record was skipped");
+    }
+    if interrupted {
+        observer.on_warning("the run was interrupted; the summaries end with the last complete day");
+    }
+    match (dew_point_column, vpd_column) {
+        (None, None) => observer.on_warning("the sensor data has no dew point or vpd columns; both were derived from temperature and humidity"),
+        (None, Some(_)) => observer.on_warning("the sensor data has no dew point column; it was derived from temperature and humidity"),
+        (Some(_), None) => observer.on_warning("the sensor data has no vpd column; it was derived from temperature and humidity"),
+        (Some(_), Some(_)) => {},
+    }
+    if clamped_vpd_records > 0 {
+        observer.on_warning(&format!("{} readings had relative humidity above 100%; their derived vpd was clamped to 0", clamped_vpd_records));
+    }
+    let rail_days = day_summaries.iter().filter(|day_summary_stats| day_summary_stats.rail_readings > 0).count();
+    if rail_days > 0 {
+        let rail_readings: usize = day_summaries.iter().map(|day_summary_stats| day_summary_stats.rail_readings).sum();
+        observer.on_warning(&format!("{} readings on {} days sat exactly at a rail value; the sensor may be saturating", rail_readings, rail_days));
+    }
+    if future_records > 0 {
+        observer.on_warning(&format!("{} records dated after {}{}", future_records, max_date, if config.drop_future { " were dropped" } else { "" }));
+    }
+    if config.interpolate_days {
+        let interpolated_days = day_summaries.interpolate_missing_days(config.max_interpolate_span(), config.gdd_base());
+        if interpolated_days > 0 {
+            observer.on_warning(&format!("interpolated {} missing days", interpolated_days));
+            for day_summary_stats in day_summaries.0.iter_mut().filter(|day_summary_stats| day_summary_stats.interpolated) {
+                day_summary_stats.apply_gdd_method(config.gdd_method, config.gdd_base(), config.degree_day_caps());
+                attach_events(day_summary_stats, &mut events_by_bucket, config);
+            }
+        }
+    }
+    observer.on_tally(&tally);
+
+    Ok(day_summaries)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    Temperature,
+    Humidity,
+    DewPoint,
+    Vpd,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [Metric::Temperature, Metric::Humidity, Metric::DewPoint, Metric::Vpd];
+
+    // The metric's position in `Metric::ALL`, and so in the registered metrics.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    // The short name the metric goes by in column headers, e.g. `temp` in `avg temp`.
+    pub fn column_name(self) -> &'static str {
+        match self {
+            Metric::Temperature => "temp",
+            Metric::Humidity => "humidity",
+            Metric::DewPoint => "dewpoint",
+            Metric::Vpd => "vpd",
+        }
+    }
+
+    // This metric's value in a reading.
+    pub fn reading<T>(self, record: &SensorRecord<T>) -> f32 {
+        match self {
+            Metric::Temperature => record.temperature,
+            Metric::Humidity => record.humidity,
+            Metric::DewPoint => record.dew_point,
+            Metric::Vpd => record.vpd,
+        }
+    }
+
+    pub fn reading_mut<T>(self, record: &mut SensorRecord<T>) -> &mut f32 {
+        match self {
+            Metric::Temperature => &mut record.temperature,
+            Metric::Humidity => &mut record.humidity,
+            Metric::DewPoint => &mut record.dew_point,
+            Metric::Vpd => &mut record.vpd,
+        }
+    }
+}
+
+// A metric summarized per day. The four built-in ones are registered as `BuiltinMetric`s ahead of
+// any custom metric (say CO₂ or light) in `Config::metrics`; see `Config::summary_metrics`. That
+// gives every metric its name, unit and place in the stats and columns, but only a custom
+// metric's readings are extracted through the trait, from each raw csv row with any configured
+// NA value already blanked. The built-in readings are parsed, validated, calibrated and derived
+// from one another as a record (see `SensorRecord::try_from_csv_record`), and read back from it
+// with `Metric::reading`, so they stay an enum listed in `Metric::ALL`.
+pub trait SummaryMetric: fmt::Debug + Send + Sync {
+    // The name used in column headers, e.g. `co2` in `avg co2`.
+    fn name(&self) -> &str;
+    fn unit(&self) -> &str;
+    // The metric's reading in a row, or None when the row has none.
+    fn extract(&self, row: &csv::StringRecord) -> Option<f32>;
+}
+
+// One of the built-in metrics, with its unit in the configured scales.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuiltinMetric {
+    pub metric: Metric,
+    pub unit: String,
+}
+
+impl SummaryMetric for BuiltinMetric {
+    fn name(&self) -> &str {
+        self.metric.column_name()
+    }
+
+    fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    // The built-in readings are parsed, validated and calibrated with the rest of the record (see
+    // `SensorRecord::try_from_csv_record`), so there's nothing left to extract from the row.
+    fn extract(&self, _row: &csv::StringRecord) -> Option<f32> {
+        None
+    }
+}
+
+// A custom metric read from a numbered column of the input, e.g. `co2:6:ppm` for the sixth
+// column, in ppm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnMetric {
+    pub name: String,
+    pub column: usize,
+    pub unit: String,
+}
+
+impl FromStr for ColumnMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(':').collect();
+        let column = match parts.get(1).map(|column| column.parse::<usize>()) {
+            Some(Ok(column)) if column > 0 && parts.len() <= 3 => column,
+            _ => return Err(format!("Invalid metric (expected name:column[:unit]): {}", s)),
+        };
+        Ok(ColumnMetric {
+            name: parts[0].to_string(),
+            column,
+            unit: parts.get(2).unwrap_or(&"").to_string(),
+        })
+    }
+}
+
+impl SummaryMetric for ColumnMetric {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    fn extract(&self, row: &csv::StringRecord) -> Option<f32> {
+        row.get(self.column - 1)?.trim().parse::<f32>().ok()
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "temperature" => Ok(Metric::Temperature),
+            "humidity" => Ok(Metric::Humidity),
+            "dew-point" => Ok(Metric::DewPoint),
+            "vpd" => Ok(Metric::Vpd),
+            _ => Err(format!("Unknown metric: {}", value)),
+        }
+    }
+}
+
+// The range of readings a metric can plausibly take; anything outside is a sensor fault.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: f32,
+    pub max: f32,
+}
+
+// A correction for a sensor's drift: readings are multiplied by `factor`, then `offset` is added.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calibration {
+    pub offset: f32,
+    pub factor: f32,
+}
+
+impl Calibration {
+    pub fn apply(self, value: f32) -> f32 {
+        value * self.factor + self.offset
+    }
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "× {} {:+}", self.factor, self.offset)
+    }
+}
+
+// Reads calibration corrections from csv rows of `metric,offset[,factor]` (with a header row),
+// e.g. `temperature,1.0` to add a degree to every temperature. The factor defaults to 1.
+pub fn read_calibration(input: impl Read) -> Result<Vec<(Metric, Calibration)>, SensoryError> {
+    let mut calibrations = Vec::new();
+    for record in csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(input).records() {
+        let record = record?;
+        let row_error = |message: String| SensoryError::Calibration(RowError {
+            line: record.position().map_or(0, |position| position.line()),
+            message,
+        });
+        let metric = record.get(0).unwrap_or("").parse::<Metric>().map_err(row_error)?;
+        let parse_number = |index: usize, name: &str, default: Option<f32>| match (record.get(index).filter(|field| !field.is_empty()), default) {
+            (Some(field), _) => field.parse::<f32>().map_err(|_| row_error(format!("invalid {}: {:?}", name, field))),
+            (None, Some(default)) => Ok(default),
+            (None, None) => Err(row_error(format!("missing {}", name))),
+        };
+        calibrations.push((metric, Calibration { offset: parse_number(1, "offset", None)?, factor: parse_number(2, "factor", Some(1.0))? }));
+    }
+    Ok(calibrations)
+}
+
+// Reads day-of-year normals in the csv layout `--climatology` writes: a header row, then the day of
+// the year (1–366, see `day_of_leap_year`), each built-in metric's mean in `Metric::ALL` order, and
+// optionally the number of years averaged. An empty mean is a missing normal.
+pub fn read_baseline(input: impl Read) -> Result<Vec<ClimatologyDay>, SensoryError> {
+    let mut baseline = Vec::new();
+    for record in csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(input).records() {
+        let record = record?;
+        let row_error = |message: String| SensoryError::Baseline(RowError {
+            line: record.position().map_or(0, |position| position.line()),
+            message,
+        });
+        let day_of_year = record.get(0).unwrap_or("");
+        let day_of_year = day_of_year.parse::<u32>().ok().filter(|day_of_year| (1..=366).contains(day_of_year))
+            .ok_or_else(|| row_error(format!("invalid day of year: {:?}", day_of_year)))?;
+        let means = (1..=Metric::ALL.len()).map(|index| match record.get(index).unwrap_or("") {
+            "" => Ok(f32::NAN),
+            mean => mean.parse::<f32>().map_err(|_| row_error(format!("invalid mean: {:?}", mean))),
+        }).collect::<Result<Vec<f32>, _>>()?;
+        let years = record.get(Metric::ALL.len() + 1).and_then(|years| years.parse().ok()).unwrap_or(0);
+        baseline.push(ClimatologyDay { day_of_year, means, years });
+    }
+    Ok(baseline)
+}
+
+// A metric's overridden bounds, as given on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetricBounds(pub Metric, pub Bounds);
+
+// Parses `metric:min:max`, e.g. `temperature:-20:120`.
+impl FromStr for MetricBounds {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.len() != 3 {
+            return Err(format!("Invalid bounds (expected metric:min:max): {}", value));
+        }
+        let parse_bound = |bound: &str| bound.parse::<f32>().map_err(|_| format!("Invalid bound: {}", bound));
+        let bounds = Bounds { min: parse_bound(parts[1])?, max: parse_bound(parts[2])? };
+        if bounds.min > bounds.max {
+            return Err(format!("Invalid bounds, min is above max: {}", value));
+        }
+
+        Ok(MetricBounds(parts[0].parse()?, bounds))
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Metric::Temperature => write!(f, "temperature"),
+            Metric::Humidity => write!(f, "humidity"),
+            Metric::DewPoint => write!(f, "dew point"),
+            Metric::Vpd => write!(f, "vpd"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Aggregate {
+    Mean,
+    Max,
+    Min,
+    Median,
+}
+
+// Formats a number in fixed decimal notation, never scientific, so that tiny values like a VPD
+// near saturation stay readable to every consumer. With `round`, that many digits follow the
+// decimal point; otherwise it's the shortest form that reads back as the same value.
+pub fn format_number(value: f32, round: Option<usize>) -> String {
+    match round {
+        Some(digits) => format!("{:.*}", digits, value),
+        None => value.to_string(),
+    }
+}
+
+// The mean of `count` values adding up to `sum`. Every mean goes through here so that an empty
+// set of values (an empty bucket, say) is an explicit None instead of a silent NaN from 0 / 0.
+pub fn safe_mean(sum: f32, count: usize) -> Option<f32> {
+    if count == 0 {
+        return None;
+    }
+
+    Some(sum / count as f32)
+}
+
+// Compensated (Kahan) summation, which keeps long running f32 sums from drifting as
+// tens of thousands of small readings are accumulated.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KahanSum {
+    sum: f32,
+    compensation: f32,
+}
+
+impl KahanSum {
+    pub fn new(value: f32) -> Self {
+        KahanSum { sum: value, compensation: 0.0 }
+    }
+
+    pub fn add(&mut self, value: f32) {
+        let adjusted = value - self.compensation;
+        let sum = self.sum + adjusted;
+        self.compensation = (sum - self.sum) - adjusted;
+        self.sum = sum;
+    }
+
+    pub fn value(&self) -> f32 {
+        self.sum
+    }
+}
+
+// Estimates a quantile of a stream in constant memory with the P² algorithm (Jain & Chlamtac,
+// 1985): five markers track the minimum, the quantile, the maximum and the two midpoints between
+// them, and are nudged along a parabola fitted through their neighbours as values arrive. Until
+// five values have been seen they are simply kept, and the estimate is exact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct P2Quantile {
+    quantile: f64,
+    count: usize,
+    heights: [f64; 5],
+    positions: [f64; 5],
+    desired_positions: [f64; 5],
+}
+
+impl P2Quantile {
+    pub fn new(quantile: f64) -> Self {
+        P2Quantile {
+            quantile,
+            count: 0,
+            heights: [0.0; 5],
+            positions: [1.0, 2.0, 3.0, 4.0, 5.0],
+            desired_positions: [1.0, 1.0 + 2.0 * quantile, 1.0 + 4.0 * quantile, 3.0 + 2.0 * quantile, 5.0],
+        }
+    }
+
+    pub fn add(&mut self, value: f32) {
+        let value = value as f64;
+        if self.count < 5 {
+            self.heights[self.count] = value;
+            self.count += 1;
+            self.heights[..self.count].sort_by(|a, b| a.partial_cmp(b).unwrap());
+            return;
+        }
+        self.count += 1;
+
+        // The cell the value falls in, widening the outer markers when it's a new extreme.
+        let cell = if value < self.heights[0] {
+            self.heights[0] = value;
+            0
+        } else if value >= self.heights[4] {
+            self.heights[4] = value;
+            3
+        } else {
+            (0..4).find(|&index| value < self.heights[index + 1]).unwrap()
+        };
+        for position in &mut self.positions[cell + 1..] {
+            *position += 1.0;
+        }
+        let increments = [0.0, self.quantile / 2.0, self.quantile, (1.0 + self.quantile) / 2.0, 1.0];
+        for (desired_position, increment) in self.desired_positions.iter_mut().zip(increments.iter()) {
+            *desired_position += increment;
+        }
+
+        for index in 1..4 {
+            let offset = self.desired_positions[index] - self.positions[index];
+            if (offset >= 1.0 && self.positions[index + 1] - self.positions[index] > 1.0)
+                || (offset <= -1.0 && self.positions[index - 1] - self.positions[index] < -1.0) {
+                let step = offset.signum();
+                let (h, n) = (&self.heights, &self.positions);
+                let parabolic = h[index] + step / (n[index + 1] - n[index - 1])
+                    * ((n[index] - n[index - 1] + step) * (h[index + 1] - h[index]) / (n[index + 1] - n[index])
+                        + (n[index + 1] - n[index] - step) * (h[index] - h[index - 1]) / (n[index] - n[index - 1]));
+                self.heights[index] = if h[index - 1] < parabolic && parabolic < h[index + 1] {
+                    parabolic
+                } else {
+                    let neighbour = (index as f64 + step) as usize;
+                    h[index] + step * (h[neighbour] - h[index]) / (n[neighbour] - n[index])
+                };
+                self.positions[index] += step;
+            }
+        }
+    }
+
+    // The quantile being estimated, from 0 to 1.
+    pub fn quantile(&self) -> f64 {
+        self.quantile
+    }
+
+    // The estimated quantile, or None before any values have been added.
+    pub fn value(&self) -> Option<f32> {
+        match self.count {
+            0 => None,
+            count if count < 5 => Some(self.heights[((count - 1) as f64 * self.quantile).round() as usize] as f32),
+            _ => Some(self.heights[2] as f32),
+        }
+    }
+}
+
+// Running stats for one metric over the readings of a day (or bucket).
+#[derive(Debug, Clone)]
+pub struct MetricStats {
+    pub max: f32,
+    pub min: f32,
+    pub mean: f32,
+    pub median: f32, // see `median()`; only kept up to date for stats without entries.
+    pub entries: Vec<f32>, // the readings themselves, unless the median is being estimated instead.
+    pub count: usize, // readings folded in, whether or not they're kept as entries.
+    pub median_estimate: Option<P2Quantile>, // when set, the median is estimated from the stream and no entries are kept.
+    pub percentile_estimate: Option<P2Quantile>, // when set, `percentile()` at its quantile is estimated from the stream.
+    pub exact_percentile: Option<(f64, f32)>, // a (fraction, value) percentile fixed by `fix_percentile`, returned in place of any estimate.
+    pub sum: KahanSum,
+    sorted_entries: OnceCell<Vec<f32>>, // the entries in ascending order, sorted on first use and cleared whenever they change.
+}
+
+impl fmt::Display for MetricStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mean: {} max: {} min: {}",
+            self.mean,
+            self.max,
+            self.min,
+        )
+    }
+}
+
+impl MetricStats {
+    // Stats of a single reading. A missing (NaN) reading leaves them without entries, to be
+    // filled in by the next reading that is present.
+    pub fn from_value(value: f32) -> Self {
+        let mut metric_stats = MetricStats {
+            max: value,
+            min: value,
+            mean: value,
+            median: value,
+            entries: Vec::new(),
+            count: 0,
+            median_estimate: None,
+            percentile_estimate: None,
+            exact_percentile: None,
+            sorted_entries: OnceCell::new(),
+            sum: KahanSum::default(),
+        };
+        metric_stats.add(value);
+        metric_stats
+    }
+
+    // Stats linearly interpolated `fraction` of the way from `before` towards `after`. They have
+    // no entries of their own.
+    pub fn interpolate(before: &Self, after: &Self, fraction: f32) -> Self {
+        let lerp = |start: f32, end: f32| start + (end - start) * fraction;
+        MetricStats {
+            max: lerp(before.max, after.max),
+            min: lerp(before.min, after.min),
+            mean: lerp(before.mean, after.mean),
+            median: lerp(before.median(), after.median()),
+            entries: Vec::new(),
+            count: 0,
+            median_estimate: None,
+            percentile_estimate: None,
+            exact_percentile: None,
+            sorted_entries: OnceCell::new(),
+            sum: KahanSum::default(),
+        }
+    }
+
+    // Folds another reading into the stats. Missing (NaN) readings are left out.
+    pub fn add(&mut self, value: f32) {
+        if value.is_nan() {
+            return;
+        }
+
+        self.sum.add(value);
+        self.count += 1;
+        self.max = self.max.max(value);
+        self.min = self.min.min(value);
+        if let Some(percentile_estimate) = self.percentile_estimate.as_mut() {
+            percentile_estimate.add(value);
+        }
+        match self.median_estimate.as_mut() {
+            Some(median_estimate) => {
+                median_estimate.add(value);
+                self.median = median_estimate.value().unwrap_or(value);
+            },
+            None => {
+                self.entries.push(value);
+                self.sorted_entries.take();
+            },
+        }
+        if let Some(mean) = safe_mean(self.sum.value(), self.count) {
+            self.mean = mean;
+        }
+    }
+
+    // Fixes the median at its exact value and drops the readings it was computed from.
+    pub fn release_entries(&mut self) {
+        self.median = self.median();
+        self.entries = Vec::new();
+        self.sorted_entries.take();
+    }
+
+    // Switches to estimating the median from the stream, so the readings no longer need to be
+    // kept. Those kept so far seed the estimate.
+    pub fn estimate_median(&mut self) {
+        let mut median_estimate = P2Quantile::new(0.5);
+        for entry in self.entries.drain(..) {
+            median_estimate.add(entry);
+        }
+        self.median = median_estimate.value().unwrap_or(self.median);
+        self.median_estimate = Some(median_estimate);
+        self.sorted_entries.take();
+    }
+
+    // The entries in ascending order. They're sorted once, the first time they're asked for,
+    // rather than on every `median()` or `percentile()`.
+    fn sorted_entries(&self) -> &[f32] {
+        self.sorted_entries.get_or_init(|| {
+            let mut sorted = self.entries.clone();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            sorted
+        })
+    }
+
+    // Starts estimating the `fraction` (0 to 1) percentile from the stream, seeded with the
+    // readings kept so far. Those are left in place, so call it before `estimate_median`.
+    pub fn estimate_percentile(&mut self, fraction: f64) {
+        let mut percentile_estimate = P2Quantile::new(fraction);
+        for &entry in &self.entries {
+            percentile_estimate.add(entry);
+        }
+        self.percentile_estimate = Some(percentile_estimate);
+    }
+
+    // Fixes the `fraction` (0 to 1) percentile at `value`, computed exactly elsewhere (see
+    // `apply_exact_medians`), for stats that no longer have the readings.
+    pub fn fix_percentile(&mut self, fraction: f64, value: f32) {
+        self.exact_percentile = Some((fraction, value));
+    }
+
+    // The middle reading (or the mean of the middle two), computed exactly from the entries when
+    // they're kept; otherwise the stored median, which is interpolated or estimated.
+    pub fn median(&self) -> f32 {
+        if self.entries.is_empty() {
+            return self.median;
+        }
+        let sorted = self.sorted_entries();
+        let middle = sorted.len() / 2;
+        if sorted.len().is_multiple_of(2) { (sorted[middle - 1] + sorted[middle]) / 2.0 } else { sorted[middle] }
+    }
+
+    // The readings at the `fraction` (0 to 1) percentile, computed with `method`. Needs the
+    // entries, so it's NaN for stats without them, unless that percentile is being estimated
+    // (see `estimate_percentile`), in which case the estimate is returned whatever the method.
+    pub fn percentile(&self, fraction: f64, method: PercentileMethod) -> f32 {
+        if let Some((_, value)) = self.exact_percentile.filter(|(exact_fraction, _)| *exact_fraction == fraction) {
+            return value;
+        }
+        if let Some(percentile_estimate) = self.percentile_estimate.filter(|estimate| estimate.quantile() == fraction) {
+            return percentile_estimate.value().unwrap_or(f32::NAN);
+        }
+        method.percentile(self.sorted_entries(), fraction)
+    }
+
+    pub fn value(&self, aggregate: Aggregate) -> f32 {
+        match aggregate {
+            Aggregate::Mean => self.mean,
+            Aggregate::Max => self.max,
+            Aggregate::Min => self.min,
+            Aggregate::Median => self.median(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DaySummaryStats<T> {
+    pub date: T,
+    pub metric_stats: Vec<MetricStats>, // stats of each registered metric, in `Config::summary_metrics` order: the built-in ones, then the custom ones.
+    pub dew_point_spread_stats: MetricStats, // temperature minus dew point per reading; the closer to 0, the closer the air is to saturation (fog, condensation).
+    pub apparent_temperature_stats: MetricStats, // wind chill or heat index per reading, when the input has a wind speed column.
+    pub interpolated: bool, // true when the day had no readings and its stats were interpolated from its neighbours.
+    pub events: Vec<String>, // events recorded within the day (or bucket), in the order they were listed.
+    pub flag: bool, // whether the `flag_column` was true for any of the day's readings.
+    pub rail_readings: usize, // readings that sat exactly at a configured rail value.
+    pub vpd_hours: f32, // integrated vpd in kPa·hours: the trapezoidal area under the vpd curve between consecutive readings.
+    pub degree_hours: Vec<f32>, // for each of `Config::gdd_bases`, the trapezoidal area under max(0, temperature - base) between consecutive readings.
+    pub degree_hours_span: f32, // the hours between consecutive readings that `degree_hours` covers.
+    pub gdd: f32, // gdd is growing degree days, a measure of heat units per day a crop receives over its lifetime relative to the minimum base temperature required for growth of that crop. e.g. corn's base temperature is 50°F. Given a day whose average temperature was 75°F, the crop would have grown by 1.5 gdd (75°F - 50°F = 15 gdd).
+}
+
+fn clamp_to_caps(temperature: f32, cap_low: Option<f32>, cap_high: Option<f32>) -> f32 {
+    let temperature = cap_low.map_or(temperature, |cap| temperature.max(cap));
+    cap_high.map_or(temperature, |cap| temperature.min(cap))
+}
+
+impl<T: Copy> DaySummaryStats<T> {
+    pub fn from_record(record: &SensorRecord<T>, gdd_base: f32) -> Self {
+        DaySummaryStats {
+            date: record.timestamp,
+            metric_stats: record.readings().map(MetricStats::from_value).collect(),
+            dew_point_spread_stats: MetricStats::from_value(record.temperature - record.dew_point),
+            apparent_temperature_stats: MetricStats::from_value(record.apparent_temperature),
+            interpolated: false,
+            events: Vec::new(),
+            flag: record.flag,
+            rail_readings: 0,
+            vpd_hours: 0.0,
+            degree_hours: Vec::new(),
+            degree_hours_span: 0.0,
+            gdd: record.temperature - gdd_base,
+        }
+    }
+
+    // A stand-in for a fully missing day, with each metric's aggregates linearly interpolated
+    // between the surrounding present days. `fraction` is how far the day lies from `before`
+    // towards `after`.
+    pub fn interpolate(date: T, before: &Self, after: &Self, fraction: f32, gdd_base: f32) -> Self {
+        let metric_stats: Vec<MetricStats> = before.metric_stats.iter().zip(after.metric_stats.iter())
+            .map(|(before, after)| MetricStats::interpolate(before, after, fraction))
+            .collect();
+        let gdd = metric_stats[Metric::Temperature.index()].mean - gdd_base;
+        DaySummaryStats {
+            date,
+            metric_stats,
+            dew_point_spread_stats: MetricStats::interpolate(&before.dew_point_spread_stats, &after.dew_point_spread_stats, fraction),
+            apparent_temperature_stats: MetricStats::interpolate(&before.apparent_temperature_stats, &after.apparent_temperature_stats, fraction),
+            interpolated: true,
+            events: Vec::new(),
+            flag: false,
+            rail_readings: 0,
+            vpd_hours: before.vpd_hours + (after.vpd_hours - before.vpd_hours) * fraction,
+            degree_hours: Vec::new(),
+            degree_hours_span: 0.0,
+            gdd,
+        }
+    }
+
+    // Folds another reading into the running stats of every registered metric.
+    pub fn update(&mut self, record: &SensorRecord<T>, gdd_base: f32) {
+        for (metric_stats, reading) in self.metric_stats.iter_mut().zip(record.readings()) {
+            metric_stats.add(reading);
+        }
+        self.dew_point_spread_stats.add(record.temperature - record.dew_point);
+        self.apparent_temperature_stats.add(record.apparent_temperature);
+        self.flag |= record.flag;
+        self.calc_growing_degrees_day(gdd_base);
+    }
+
+    pub fn stats(&self, metric: Metric) -> &MetricStats {
+        &self.metric_stats[metric.index()]
+    }
+
+    // The stats of the registered custom metrics, in registration order.
+    pub fn custom_stats(&self) -> &[MetricStats] {
+        &self.metric_stats[Metric::ALL.len()..]
+    }
+
+    // Keeps the exact medians but drops the readings, once the day is complete (see
+    // `MetricStats::release_entries`).
+    pub fn release_entries(&mut self) {
+        for metric_stats in &mut self.metric_stats {
+            metric_stats.release_entries();
+        }
+        self.dew_point_spread_stats.release_entries();
+        self.apparent_temperature_stats.release_entries();
+    }
+
+    // Estimates each metric's `fraction` (0 to 1) percentile from the stream (see
+    // `MetricStats::estimate_percentile`).
+    pub fn estimate_percentiles(&mut self, fraction: f64) {
+        for metric_stats in &mut self.metric_stats {
+            metric_stats.estimate_percentile(fraction);
+        }
+    }
+
+    // Estimates every metric's median from here on instead of keeping its readings (see
+    // `MetricStats::estimate_median`).
+    pub fn estimate_medians(&mut self) {
+        for metric_stats in &mut self.metric_stats {
+            metric_stats.estimate_median();
+        }
+        self.dew_point_spread_stats.estimate_median();
+        self.apparent_temperature_stats.estimate_median();
+    }
+
+    pub fn calc_growing_degrees_day(&mut self, gdd_base: f32) {
+        // The running gdd from the day's mean so far; `apply_gdd_method` replaces it with the
+        // configured method's once the day is complete, e.g. the hourly method's integrated
+        // degree-hours across the day and night.
+        self.gdd = self.stats(Metric::Temperature).mean - gdd_base;
+    }
+
+    // Recomputes the day's gdd with the given method once all of its readings are in. The caps
+    // only apply to the modified method.
+    pub fn apply_gdd_method(&mut self, method: GddMethod, gdd_base: f32, caps: (Option<f32>, Option<f32>)) {
+        self.gdd = self.gdd_for_base(method, gdd_base, caps, 0);
+    }
+
+    // The day's gdd against a base, `base_index` being its position in `Config::gdd_bases`; the
+    // hourly method uses the degree-hours integrated against it.
+    pub fn gdd_for_base(&self, method: GddMethod, gdd_base: f32, caps: (Option<f32>, Option<f32>), base_index: usize) -> f32 {
+        let raw_gdd = self.raw_gdd_for_base(method, gdd_base, caps, base_index);
+        match method {
+            GddMethod::Mean | GddMethod::BaseFloor => raw_gdd,
+            GddMethod::MinMax | GddMethod::Hourly | GddMethod::Modified => raw_gdd.max(0.0),
+        }
+    }
+
+    // The day's gdd as `gdd_for_base` computes it, but before the methods that floor it at zero
+    // do so. A cold day's is negative.
+    pub fn raw_gdd_for_base(&self, method: GddMethod, gdd_base: f32, (cap_low, cap_high): (Option<f32>, Option<f32>), base_index: usize) -> f32 {
+        let (max, min) = (self.stats(Metric::Temperature).max, self.stats(Metric::Temperature).min);
+        match (method, self.degree_hours.get(base_index)) {
+            (GddMethod::Mean, _) => self.stats(Metric::Temperature).mean - gdd_base,
+            (GddMethod::MinMax, _) => (max + min) / 2.0 - gdd_base,
+            (GddMethod::BaseFloor, _) => (max.max(gdd_base) + min.max(gdd_base)) / 2.0 - gdd_base,
+            (GddMethod::Hourly, Some(degree_hours)) if self.degree_hours_span > 0.0 => degree_hours / 24.0,
+            (GddMethod::Hourly, _) => (max + min) / 2.0 - gdd_base,
+            (GddMethod::Modified, _) => (clamp_to_caps(max, cap_low, cap_high) + clamp_to_caps(min, cap_low, cap_high)) / 2.0 - gdd_base,
+        }
+    }
+
+    pub fn value(&self, metric: Metric, aggregate: Aggregate) -> f32 {
+        self.stats(metric).value(aggregate)
+    }
+
+    // Degree days relative to an arbitrary base. The day's max and min temperatures are first
+    // clamped to the optional caps, averaged, and the difference from the base in the given
+    // direction is floored at zero.
+    pub fn degree_day(&self, base: f32, cap_low: Option<f32>, cap_high: Option<f32>, direction: DegreeDayDirection) -> f32 {
+        let mean = (clamp_to_caps(self.stats(Metric::Temperature).max, cap_low, cap_high) + clamp_to_caps(self.stats(Metric::Temperature).min, cap_low, cap_high)) / 2.0;
+
+        match direction {
+            DegreeDayDirection::Above => (mean - base).max(0.0),
+            DegreeDayDirection::Below => (base - mean).max(0.0),
+        }
+    }
+
+    pub fn reading_count(&self) -> usize {
+        self.stats(Metric::Temperature).count
+    }
+
+    // Percentage of the readings expected for a full day at the given logging interval, capped at 100%.
+    pub fn completeness(&self, interval: Interval) -> f32 {
+        let expected_count = (SECONDS_PER_DAY / interval.0.as_secs()).max(1) as f32;
+        (self.reading_count() as f32 / expected_count * 100.0).min(100.0)
+    }
+
+    // A step-by-step breakdown of how the day's GDD (and configured degree days) were computed.
+    pub fn explain_gdd(&self, config: &Config) -> String
+    where
+        T: fmt::Display,
+    {
+        let gdd_base = config.gdd_base();
+        let mut explanation = format!("GDD for {}\n", self.date);
+        explanation += format!("  readings: {}\n", self.reading_count()).as_str();
+        explanation += format!("  max temperature: {}\n", self.stats(Metric::Temperature).max).as_str();
+        explanation += format!("  min temperature: {}\n", self.stats(Metric::Temperature).min).as_str();
+        explanation += format!("  mean temperature: {}\n", self.stats(Metric::Temperature).mean).as_str();
+        let (cap_low, cap_high) = config.degree_day_caps();
+        let format_cap = |cap: Option<f32>| cap.map_or("none".to_string(), |cap| cap.to_string());
+        match config.gdd_method {
+            GddMethod::Modified => explanation += format!("  caps: low {} high {}\n", format_cap(cap_low), format_cap(cap_high)).as_str(),
+            _ => explanation += "  caps: none\n",
+        }
+        explanation += format!("  base: {}\n", gdd_base).as_str();
+        let (max, min) = (self.stats(Metric::Temperature).max, self.stats(Metric::Temperature).min);
+        match config.gdd_method {
+            GddMethod::Mean => {
+                explanation += "  formula: gdd = mean temperature - base\n";
+                explanation += format!("  gdd: {} - {} = {}\n", self.stats(Metric::Temperature).mean, gdd_base, self.gdd).as_str();
+            },
+            GddMethod::MinMax => {
+                explanation += "  formula: gdd = max(0, (max temperature + min temperature) / 2 - base)\n";
+                explanation += format!("  gdd: max(0, ({} + {}) / 2 - {}) = {}\n", max, min, gdd_base, self.gdd).as_str();
+            },
+            GddMethod::BaseFloor => {
+                explanation += "  formula: gdd = (max(max temperature, base) + max(min temperature, base)) / 2 - base\n";
+                explanation += format!("  gdd: ({} + {}) / 2 - {} = {}\n", max.max(gdd_base), min.max(gdd_base), gdd_base, self.gdd).as_str();
+            },
+            GddMethod::Hourly if self.degree_hours_span > 0.0 => {
+                explanation += format!("  formula: gdd = degree-hours above base / 24, over the {} hours between readings\n", self.degree_hours_span).as_str();
+                explanation += format!("  gdd: {} / 24 = {}\n", self.degree_hours[0], self.gdd).as_str();
+            },
+            GddMethod::Hourly => {
+                explanation += "  formula: gdd = max(0, (max temperature + min temperature) / 2 - base), as there are too few readings to integrate\n";
+                explanation += format!("  gdd: max(0, ({} + {}) / 2 - {}) = {}\n", max, min, gdd_base, self.gdd).as_str();
+            },
+            GddMethod::Modified => {
+                explanation += "  formula: gdd = max(0, (capped max + capped min) / 2 - base)\n";
+                explanation += format!("  gdd: max(0, ({} + {}) / 2 - {}) = {}\n", clamp_to_caps(max, cap_low, cap_high), clamp_to_caps(min, cap_low, cap_high), gdd_base, self.gdd).as_str();
+            },
+        }
+
+        if let Some(base) = config.degree_day_base {
+            let capped_max = clamp_to_caps(self.stats(Metric::Temperature).max, cap_low, cap_high);
+            let capped_min = clamp_to_caps(self.stats(Metric::Temperature).min, cap_low, cap_high);
+            explanation += format!("Degree days ({:?} {})\n", config.degree_day_direction, base).as_str();
+            explanation += format!("  caps: low {} high {}\n", format_cap(cap_low), format_cap(cap_high)).as_str();
+            explanation += format!("  capped max temperature: {}\n", capped_max).as_str();
+            explanation += format!("  capped min temperature: {}\n", capped_min).as_str();
+            explanation += format!("  base: {}\n", base).as_str();
+            explanation += match config.degree_day_direction {
+                DegreeDayDirection::Above => "  formula: degree days = max(0, (capped max + capped min) / 2 - base)\n",
+                DegreeDayDirection::Below => "  formula: degree days = max(0, base - (capped max + capped min) / 2)\n",
+            };
+            explanation += format!("  degree days: {}\n", self.degree_day(base, cap_low, cap_high, config.degree_day_direction)).as_str();
+        }
+
+        explanation
+    }
+
+    // The readings a metric's stats were computed from, in the order they were read. Interpolated
+    // days have none.
+    pub fn entries(&self, metric: Metric) -> &[f32] {
+        &self.stats(metric).entries
+    }
+
+    // Population standard deviation of a metric's readings, or None for a day without readings.
+    pub fn std_dev(&self, metric: Metric) -> Option<f32> {
+        let entries = self.entries(metric);
+        let mean = self.value(metric, Aggregate::Mean);
+        let variance = safe_mean(entries.iter().map(|entry| (entry - mean).powi(2)).sum(), entries.len())?;
+
+        Some(variance.sqrt())
+    }
+
+    // Coefficient of variation (std / mean), which normalizes variability across metrics with
+    // different scales. None when the mean is zero, as the ratio is undefined.
+    pub fn cv(&self, metric: Metric) -> Option<f32> {
+        let mean = self.value(metric, Aggregate::Mean);
+        if mean == 0.0 {
+            return None;
+        }
+
+        self.std_dev(metric).map(|std_dev| std_dev / mean)
+    }
+
+    // Whether the cumulative gdd starts over on this day: it has a `GDD_RESET_EVENT`.
+    pub fn resets_gdd(&self) -> bool {
+        self.events.iter().any(|event| event.trim().eq_ignore_ascii_case(GDD_RESET_EVENT))
+    }
+
+    // A freeze day is any day whose minimum temperature reached the freeze threshold.
+    pub fn is_freeze_day(&self, freeze_threshold: f32) -> bool {
+        self.stats(Metric::Temperature).min <= freeze_threshold
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use super::*;
+
+    // The tolerance for comparing computed f32 statistics with hand-computed ones. It is loose
+    // enough to absorb the rounding of sums and means over the small fixtures used here, and
+    // tight enough that an off-by-one reading or a wrong formula still fails.
+    const DEFAULT_EPSILON: f32 = 1e-4;
+
+    // Asserts that `a` is within `eps` of `b`.
+    fn assert_close(a: f32, b: f32, eps: f32) {
+        assert!((a - b).abs() <= eps, "{} is not within {} of {}", a, eps, b);
+    }
+
+    fn record(date: &str, temperature: f32, humidity: f32) -> SensorRecord<NaiveDate> {
+        let timestamp = format!("{} 12:00:00", date);
+        let fields = vec![timestamp, temperature.to_string(), humidity.to_string(), "50".to_string(), "1".to_string()];
+        SensorRecord::from_csv_record(csv::StringRecord::from(fields)).unwrap()
+    }
+
+    fn config(args: &[&str]) -> Config {
+        Config::from_args(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    #[test]
+    fn series_returns_one_point_per_day() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2021-01-01", 60.0, 40.0),
+            record("2021-01-01", 70.0, 60.0),
+            record("2021-01-02", 50.0, 80.0),
+            record("2021-01-02", 54.0, 70.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+
+        let first: NaiveDate = "2021-01-01".parse().unwrap();
+        let second: NaiveDate = "2021-01-02".parse().unwrap();
+        assert_eq!(day_summaries.series(Metric::Temperature, Aggregate::Mean), vec![(first, 65.0), (second, 52.0)]);
+        assert_eq!(day_summaries.series(Metric::Humidity, Aggregate::Max), vec![(first, 60.0), (second, 80.0)]);
+    }
+
+    #[test]
+    fn celsius_freeze_default_is_zero_degrees() {
+        let config = config(&["--celsius"]);
+        assert_close(config.freeze_threshold(), 0.0, DEFAULT_EPSILON);
+
+        let mut day_summaries = DaySummaries(Vec::new());
+        day_summaries.add_record(&record("2021-01-01", 0.0, 50.0), &config);
+        day_summaries.add_record(&record("2021-01-02", 0.5, 50.0), &config);
+        assert!(day_summaries.0[0].is_freeze_day(config.freeze_threshold()));
+        assert!(!day_summaries.0[1].is_freeze_day(config.freeze_threshold()));
+    }
+
+    #[test]
+    fn capped_degree_days_accumulate_in_either_direction() {
+        let config = config(&["--degree-day-base", "65", "--degree-day-direction", "below", "--degree-day-cap-low", "50", "--degree-day-cap-high", "86"]);
+        assert_eq!(config.degree_day_direction, DegreeDayDirection::Below);
+        let (cap_low, cap_high) = (config.degree_day_cap_low, config.degree_day_cap_high);
+
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2021-07-01", 95.0, 50.0), record("2021-07-01", 40.0, 50.0),
+            record("2021-07-02", 60.0, 50.0), record("2021-07-02", 30.0, 50.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+        let (hot, cold) = (&day_summaries.0[0], &day_summaries.0[1]);
+
+        // Capped to 86 and 50, the hot day averages 68: 3 above 65 and none below it.
+        assert_close(hot.degree_day(65.0, cap_low, cap_high, DegreeDayDirection::Above), 3.0, DEFAULT_EPSILON);
+        assert_close(hot.degree_day(65.0, cap_low, cap_high, DegreeDayDirection::Below), 0.0, DEFAULT_EPSILON);
+        // Uncapped it averages 67.5.
+        assert_close(hot.degree_day(65.0, None, None, DegreeDayDirection::Above), 2.5, DEFAULT_EPSILON);
+        // The cold day's min is raised to the low cap, so it averages 55: 10 below 65.
+        assert_close(cold.degree_day(65.0, cap_low, cap_high, DegreeDayDirection::Below), 10.0, DEFAULT_EPSILON);
+        assert_close(cold.degree_day(65.0, None, None, DegreeDayDirection::Below), 20.0, DEFAULT_EPSILON);
+
+        let accumulated: f32 = day_summaries.iter().map(|day| day.degree_day(65.0, cap_low, cap_high, config.degree_day_direction)).sum();
+        assert_close(accumulated, 10.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn half_the_expected_readings_is_fifty_percent_complete() {
+        // Hourly logging expects 24 readings a day; the first day has 12 and the second 30.
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for _ in 0..12 {
+            day_summaries.add_record(&record("2021-01-01", 60.0, 40.0), &config);
+        }
+        for _ in 0..30 {
+            day_summaries.add_record(&record("2021-01-02", 60.0, 40.0), &config);
+        }
+        let hourly: Interval = "1h".parse().unwrap();
+        assert_close(day_summaries.0[0].completeness(hourly), 50.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[1].completeness(hourly), 100.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn intervals_parse_in_seconds_minutes_or_hours() {
+        assert_eq!("90s".parse::<Interval>(), Ok(Interval(Duration::from_secs(90))));
+        assert_eq!("15m".parse::<Interval>(), Ok(Interval(Duration::from_secs(15 * 60))));
+        assert_eq!("2h".parse::<Interval>(), Ok(Interval(Duration::from_secs(2 * 60 * 60))));
+        for invalid in &["15µ", "µ", "", "15", "h", "-1h", "0m", "18446744073709551615h"] {
+            assert!(invalid.parse::<Interval>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn stats_for_a_two_day_window_aggregate_into_one_bucket() {
+        let dataset = SensorDataset(vec![
+            record("2021-01-01", 50.0, 40.0),
+            record("2021-01-02", 60.0, 50.0),
+            record("2021-01-02", 64.0, 70.0),
+            record("2021-01-03", 80.0, 90.0),
+        ]);
+        let date = |date: &str| date.parse::<NaiveDate>().unwrap();
+        let window = dataset.stats_for_range(date("2021-01-02"), date("2021-01-03"), &Config::default()).unwrap();
+        assert_eq!(window.date, date("2021-01-02"));
+        assert_eq!(window.reading_count(), 3);
+        assert_close(window.value(Metric::Temperature, Aggregate::Mean), 68.0, DEFAULT_EPSILON);
+        assert_close(window.value(Metric::Humidity, Aggregate::Max), 90.0, DEFAULT_EPSILON);
+        assert_close(window.value(Metric::Temperature, Aggregate::Min), 60.0, DEFAULT_EPSILON);
+        assert!(dataset.stats_for_range(date("2021-02-01"), date("2021-02-28"), &Config::default()).is_none());
+
+        // The window's gdd follows the configured method: mean 68 against min-max (80 + 60) / 2.
+        let gdd = |args: &[&str]| dataset.stats_for_range(date("2021-01-02"), date("2021-01-03"), &config(args)).unwrap().gdd;
+        assert_close(gdd(&["--gdd-base", "50"]), 18.0, DEFAULT_EPSILON);
+        assert_close(gdd(&["--gdd-method", "min-max", "--gdd-base", "50"]), 20.0, DEFAULT_EPSILON);
+        assert_close(gdd(&["--gdd-method", "modified", "--gdd-base", "50", "--degree-day-cap-high", "70"]), 15.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn epoch_records_parse_without_chrono_types() {
+        let record = SensorRecord::try_from_epoch_csv_record(&csv::StringRecord::from(vec!["90061", "60.5", "40", "35", "1.2"])).unwrap();
+        assert_eq!(record.timestamp, EpochDay(1));
+        assert_eq!(record.seconds_of_day, 3661);
+        assert_close(record.temperature, 60.5, DEFAULT_EPSILON);
+
+        match SensorRecord::try_from_epoch_csv_record(&csv::StringRecord::from(vec!["2021-01-01", "60.5", "40", "35", "1.2"])) {
+            Err(SensoryError::Parse(err)) => assert_eq!(err.message, "invalid epoch timestamp: \"2021-01-01\""),
+            other => panic!("expected a parse error, got {:?}", other.map(|record| record.timestamp)),
+        }
+        match SensorRecord::from_epoch_csv_record(csv::StringRecord::from(vec!["90061", "60.5", "40"])) {
+            Err(SensoryError::Parse(err)) => assert_eq!(err.message, "expected 5 fields, found 3"),
+            other => panic!("expected a parse error, got {:?}", other.map(|record| record.timestamp)),
+        }
+    }
+
+    #[test]
+    fn weekly_buckets_use_the_iso_week_year_across_new_year() {
+        let config = config(&["--granularity", "week"]);
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[record("2020-12-27", 40.0, 40.0), record("2020-12-31", 42.0, 40.0), record("2021-01-02", 44.0, 40.0), record("2021-01-04", 46.0, 40.0)] {
+            day_summaries.add_record_grouped(record, &config);
+        }
+
+        let dates: Vec<String> = day_summaries.iter().map(|week| week.date.to_string()).collect();
+        assert_eq!(dates, vec!["2020-12-21", "2020-12-28", "2021-01-04"]);
+        assert_close(day_summaries.0[1].value(Metric::Temperature, Aggregate::Mean), 43.0, DEFAULT_EPSILON);
+
+        let key = |date: &str| Granularity::Week.bucket_key(date.parse().unwrap(), &config.calendar);
+        assert_eq!(key("2021-01-02"), BucketKey::Week(2020, 53));
+        assert_eq!(key("2021-01-04"), BucketKey::Week(2021, 1));
+        // The last days of 2019 fall in the first ISO week of 2020, a year apart from 2020's.
+        assert_eq!(key("2019-12-31"), BucketKey::Week(2020, 1));
+        assert_ne!(key("2019-12-31"), key("2020-12-31"));
+    }
+
+    #[test]
+    fn hour_of_day_profile_averages_each_hour_across_days() {
+        // Two days of hourly vpd: the second day's readings are 0.2 kPa higher than the first's.
+        let mut profile = HourOfDayProfile::default();
+        for day in 0..2 {
+            for hour in 0..24 {
+                let vpd = 0.5 + (12 - (hour as i32 - 14).abs()) as f32 * 0.1 + day as f32 * 0.2;
+                profile.add(hour, vpd);
+            }
+        }
+        let means = profile.means();
+        assert_eq!(means.len(), 24);
+        assert_close(means[0].unwrap(), 0.4, DEFAULT_EPSILON);
+        assert_close(means[14].unwrap(), 1.8, DEFAULT_EPSILON);
+        let (driest_hour, driest_vpd) = profile.max_hour().unwrap();
+        assert_eq!(driest_hour, 14);
+        assert_close(driest_vpd, 1.8, DEFAULT_EPSILON);
+        assert_eq!(profile.min_hour().map(|(hour, _)| hour), Some(0));
+
+        assert!(HourOfDayProfile::default().means().iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn kahan_sum_stays_accurate_where_a_naive_f32_sum_drifts() {
+        // A million readings of 0.1 sum to 100000.
+        let mut naive = 0.0f32;
+        let mut kahan = KahanSum::default();
+        for _ in 0..1_000_000 {
+            naive += 0.1;
+            kahan.add(0.1);
+        }
+        assert!((naive - 100_000.0).abs() > 100.0, "the naive sum didn't drift: {}", naive);
+        assert_close(kahan.value(), 100_000.0, 0.01);
+
+        let mut metric_stats = MetricStats::from_value(0.1);
+        for _ in 1..1_000_000 {
+            metric_stats.add(0.1);
+        }
+        assert_close(metric_stats.mean, 0.1, 1e-6);
+    }
+
+    #[test]
+    fn units_row_sets_celsius() {
+        let mut config = config(&["--units-row"]);
+        config.apply_units_row(&csv::StringRecord::from(vec!["time", "°C", "%", "°C", "kPa"])).unwrap();
+        assert_eq!(config.scale, TemperatureScale::Celsius);
+        assert_close(config.freeze_threshold(), 0.0, DEFAULT_EPSILON);
+
+        let unknown = csv::StringRecord::from(vec!["time", "K", "%", "K", "kPa"]);
+        assert!(config.apply_units_row(&unknown).is_err());
+    }
+
+    #[test]
+    fn fit_gdd_base_recovers_the_base_development_tracks() {
+        // Each interval between events accumulates exactly 100 gdd against a base of 45, over a
+        // different number of days, so any other base makes the intervals uneven.
+        let intervals: [&[f32]; 4] = [&[95.0, 95.0], &[70.0, 70.0, 70.0, 70.0], &[65.0, 65.0, 65.0, 65.0, 65.0], &[80.0, 80.0, 75.0]];
+        let mut date: NaiveDate = "2021-05-01".parse().unwrap();
+        let mut day_summaries = DaySummaries(Vec::new());
+        day_summaries.add_record(&record(&date.to_string(), 60.0, 50.0), &Config::default());
+        let mut event_dates = vec![date];
+        for interval in &intervals {
+            for &temperature in interval.iter() {
+                date = date.succ();
+                day_summaries.add_record(&record(&date.to_string(), temperature, 50.0), &Config::default());
+            }
+            event_dates.push(date);
+        }
+
+        let fit = day_summaries.fit_gdd_base(&event_dates, (30..=60).map(|base| base as f32)).unwrap();
+        assert_close(fit.base, 45.0, DEFAULT_EPSILON);
+        assert_close(fit.cv, 0.0, DEFAULT_EPSILON);
+        assert!(day_summaries.fit_gdd_base(&event_dates[..2], (30..=60).map(|base| base as f32)).is_none());
+    }
+
+    #[test]
+    fn supersaturated_humidity_clamps_the_derived_vpd_to_zero() {
+        let mut supersaturated = record("2021-01-01", 60.0, 105.0);
+        assert!(supersaturated.derive_vpd(TemperatureScale::Fahrenheit));
+        assert_close(supersaturated.vpd, 0.0, DEFAULT_EPSILON);
+
+        let mut humid = record("2021-01-01", 60.0, 50.0);
+        assert!(!humid.derive_vpd(TemperatureScale::Fahrenheit));
+        assert_close(humid.vpd, vapor_pressure_deficit(TemperatureScale::Fahrenheit.to_celsius(60.0), 50.0), DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn week_start_shifts_the_weekly_buckets() {
+        // A Saturday, Sunday and Monday.
+        let records = [record("2021-06-05", 60.0, 40.0), record("2021-06-06", 62.0, 40.0), record("2021-06-07", 64.0, 40.0)];
+        let week_starts = |args: &[&str]| -> Vec<(String, usize)> {
+            let config = config(args);
+            let mut day_summaries = DaySummaries(Vec::new());
+            for record in &records {
+                day_summaries.add_record_grouped(record, &config);
+            }
+            day_summaries.iter().map(|week| (week.date.to_string(), week.reading_count())).collect()
+        };
+        let by_monday = week_starts(&["--granularity", "week"]);
+        assert_eq!(by_monday, vec![("2021-05-31".to_string(), 2), ("2021-06-07".to_string(), 1)]);
+        assert_eq!(week_starts(&["--granularity", "week", "--week-start", "mon"]), by_monday);
+        assert_eq!(week_starts(&["--granularity", "week", "--week-start", "sun"]), vec![("2021-05-30".to_string(), 1), ("2021-06-06".to_string(), 2)]);
+    }
+
+    #[test]
+    fn frosts_in_march_and_october_bound_the_frost_free_span() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for (date, temperature) in &[("2021-03-10", 30.0), ("2021-03-20", 31.0), ("2021-03-21", 45.0), ("2021-10-14", 29.0), ("2021-10-30", 25.0), ("2022-06-01", 70.0)] {
+            day_summaries.add_record(&record(date, *temperature, 50.0), &config);
+        }
+        let frost_seasons = day_summaries.frost_seasons(config.freeze_threshold());
+        assert_eq!(frost_seasons.len(), 2);
+        assert_eq!(frost_seasons[0].last_spring_frost, Some("2021-03-20".parse().unwrap()));
+        assert_eq!(frost_seasons[0].first_fall_frost, Some("2021-10-14".parse().unwrap()));
+        // March 21st through October 13th.
+        assert_eq!(frost_seasons[0].frost_free_days(), Some(207));
+        assert_eq!(frost_seasons[1], FrostSeason { year: 2022, last_spring_frost: None, first_fall_frost: None });
+        assert_eq!(frost_seasons[1].frost_free_days(), None);
+    }
+
+    #[test]
+    fn a_missing_middle_day_is_interpolated() {
+        let summarize = |config: &Config| {
+            let mut day_summaries = DaySummaries(Vec::new());
+            for (date, temperature, humidity, vpd) in &[("2021-01-01", 60.0, 40.0, 1.0), ("2021-01-03", 70.0, 60.0, 2.0), ("2021-01-06", 70.0, 60.0, 2.0)] {
+                let mut record = record(date, *temperature, *humidity);
+                record.vpd = *vpd;
+                day_summaries.add_record(&record, config);
+            }
+            day_summaries.interpolate_missing_days(config.max_interpolate_span(), config.gdd_base());
+            day_summaries
+        };
+        let day_summaries = summarize(&config(&["--interpolate-days"]));
+
+        let dates: Vec<String> = day_summaries.iter().map(|day| day.date.to_string()).collect();
+        assert_eq!(dates, vec!["2021-01-01", "2021-01-02", "2021-01-03", "2021-01-06"]);
+        let interpolated = &day_summaries.0[1];
+        assert!(interpolated.interpolated);
+        assert_close(interpolated.value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+        assert_close(interpolated.value(Metric::Humidity, Aggregate::Max), 50.0, DEFAULT_EPSILON);
+        assert_close(interpolated.value(Metric::Vpd, Aggregate::Mean), 1.5, DEFAULT_EPSILON);
+        // The two-day gap is longer than the default span of one day, so it's left alone.
+        assert!(day_summaries.iter().filter(|day| day.interpolated).count() == 1);
+
+        let day_summaries = summarize(&config(&["--interpolate-days", "--max-interpolate-span", "2"]));
+        assert_eq!(day_summaries.0.len(), 6);
+    }
+
+    #[test]
+    fn schema_check_rejects_a_reordered_header_and_passes_the_matching_one() {
+        let rows = "2021-01-01 06:00:00,60,40,35,1.0\n";
+        let matching = format!("{}{}", HEADER, rows);
+        assert_eq!(process(&config(&["--schema-check"]), matching.as_bytes(), None::<&[u8]>).unwrap().0.len(), 1);
+
+        let reordered = format!("timestamp,humidity,temperature,dewpoint,vpd\n{}", rows);
+        match process(&config(&["--schema-check"]), reordered.as_bytes(), None::<&[u8]>) {
+            Err(err @ SensoryError::Schema(_)) => assert_eq!(err.to_string().lines().skip(1).collect::<Vec<_>>(), vec![
+                "  column 2: expected \"temperature\", found \"humidity\"",
+                "  column 3: expected \"humidity\", found \"temperature\"",
+            ]),
+            other => panic!("expected a schema error, got {:?}", other.map(|day_summaries| day_summaries.0.len())),
+        }
+
+        let custom = config(&["--expected-columns", "timestamp,humidity,temperature,dewpoint,vpd"]);
+        assert!(process(&custom, reordered.as_bytes(), None::<&[u8]>).is_ok());
+    }
+
+    #[test]
+    fn cv_is_std_over_mean_and_undefined_for_a_zero_mean() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2021-01-01", 60.0, 40.0),
+            record("2021-01-01", 70.0, 60.0),
+            record("2021-01-02", -5.0, 50.0),
+            record("2021-01-02", 5.0, 50.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+
+        assert_close(day_summaries.0[0].cv(Metric::Temperature).unwrap(), 5.0 / 65.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[0].cv(Metric::Humidity).unwrap(), 10.0 / 50.0, DEFAULT_EPSILON);
+        assert_eq!(day_summaries.0[1].cv(Metric::Temperature), None);
+        assert_eq!(day_summaries.0[1].cv(Metric::Humidity), Some(0.0));
+    }
+
+    const HEADER: &str = "timestamp,temperature,humidity,dewpoint,vpd\n";
+
+    #[test]
+    fn process_summarizes_in_memory_data() {
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 18:00:00,70,60,45,1.4\n2021-01-02 12:00:00,50,80,44,0.3\n", HEADER);
+        let events = "timestamp,event\n2021-01-02 08:00:00,planted\n";
+        let day_summaries = process(&Config::default(), input.as_bytes(), Some(events.as_bytes())).unwrap();
+
+        assert_eq!(day_summaries.0.len(), 2);
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[1].value(Metric::Humidity, Aggregate::Max), 80.0, DEFAULT_EPSILON);
+        assert!(day_summaries.0[0].events.is_empty());
+        assert_eq!(day_summaries.0[1].events, vec!["planted"]);
+
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert!(day_summaries.iter().all(|day_summary| day_summary.events.is_empty()));
+    }
+
+    #[test]
+    fn minus_9999_is_missing_and_left_out_of_the_stats() {
+        let input = format!("{}2021-01-01 06:00:00,-9999,40,35,1.0\n2021-01-01 12:00:00,60,NA,35,1.0\n2021-01-01 18:00:00,70,60,45,2.0\n", HEADER);
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Min), 60.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[0].value(Metric::Humidity, Aggregate::Mean), 50.0, DEFAULT_EPSILON);
+
+        // An explicit list replaces the defaults, so -9999 is read as an (implausible) reading again.
+        match process(&config(&["--na-values", "NA"]), input.as_bytes(), None::<&[u8]>) {
+            Err(SensoryError::Parse(err)) => assert_eq!(err.message, "temperature -9999 is outside the plausible range -76..158"),
+            other => panic!("expected a parse error, got {:?}", other.map(|day_summaries| day_summaries.0.len())),
+        }
+    }
+
+    #[test]
+    fn season_stats_find_the_global_max_and_its_date() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2021-06-01", 70.0, 40.0),
+            record("2021-06-01", 80.0, 60.0),
+            record("2021-06-02", 95.0, 30.0),
+            record("2021-06-02", 65.0, 20.0),
+            record("2021-06-03", 60.0, 90.0),
+            record("2021-06-03", 95.0, 50.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+
+        let season_stats = day_summaries.season_stats();
+        let temperature = season_stats.iter().find(|season_stats| season_stats.metric == Metric::Temperature).unwrap();
+        assert_close(temperature.max, 95.0, DEFAULT_EPSILON);
+        // The 95 reached again on the third day doesn't move the date off the first to reach it.
+        assert_eq!(temperature.max_date.to_string(), "2021-06-02");
+        assert_close(temperature.min, 60.0, DEFAULT_EPSILON);
+        assert_eq!(temperature.min_date.to_string(), "2021-06-03");
+        assert_close(temperature.mean, 77.5, DEFAULT_EPSILON);
+
+        let humidity = season_stats.iter().find(|season_stats| season_stats.metric == Metric::Humidity).unwrap();
+        assert_close(humidity.max, 90.0, DEFAULT_EPSILON);
+        assert_eq!(humidity.max_date.to_string(), "2021-06-03");
+    }
+
+    #[test]
+    fn entries_are_a_days_readings_in_the_order_they_were_read() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2021-01-01", 70.0, 40.0),
+            record("2021-01-01", 60.5, 60.0),
+            record("2021-01-01", 65.0, 50.0),
+            record("2021-01-02", 50.0, 80.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[70.0, 60.5, 65.0]);
+        assert_eq!(day_summaries.0[0].entries(Metric::Humidity).len(), 3);
+        assert_eq!(day_summaries.0[1].entries(Metric::Temperature), &[50.0]);
+    }
+
+    #[test]
+    fn sample_every_three_ingests_every_third_record() {
+        let rows: String = (0..8).map(|hour| format!("2021-01-01 {:02}:00:00,{},40,35,1.0\n", hour, 60 + hour)).collect();
+        let input = format!("{}{}", HEADER, rows);
+        let day_summaries = process(&config(&["--sample-every", "3"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 63.0, 66.0]);
+
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature).len(), 8);
+    }
+
+    #[test]
+    fn readings_around_midnight_belong_to_the_agronomic_day() {
+        let input = format!(
+            "{}2021-01-01 07:00:00,60,40,35,1.0\n2021-01-01 23:30:00,50,40,35,1.0\n2021-01-02 00:30:00,48,40,35,1.0\n2021-01-02 05:59:00,46,40,35,1.0\n2021-01-02 06:00:00,55,40,35,1.0\n",
+            HEADER,
+        );
+        let day_summaries = process(&config(&["--day-start-hour", "6"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        let dates: Vec<String> = day_summaries.iter().map(|day| day.date.to_string()).collect();
+        assert_eq!(dates, vec!["2021-01-01", "2021-01-02"]);
+        // The night from 23:30 to 05:59 stays with the day it began on.
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 50.0, 48.0, 46.0]);
+        assert_eq!(day_summaries.0[1].entries(Metric::Temperature), &[55.0]);
+
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 50.0]);
+    }
+
+    // Records the rejected lines and the final tally of a read.
+    #[derive(Default)]
+    struct Rejects {
+        lines: Vec<u64>,
+        tally: RowTally,
+    }
+
+    impl ProcessObserver for Rejects {
+        fn on_reject(&mut self, _raw: &csv::StringRecord, err: &RowError) -> Result<(), SensoryError> {
+            self.lines.push(err.line);
+            Ok(())
+        }
+
+        fn on_tally(&mut self, tally: &RowTally) {
+            self.tally = *tally;
+        }
+    }
+
+    #[test]
+    fn an_overridden_temperature_bound_flags_an_otherwise_valid_reading() {
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 12:00:00,105,40,35,1.0\n", HEADER);
+        assert_eq!(process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap().0[0].entries(Metric::Temperature), &[60.0, 105.0]);
+
+        let bounded = config(&["--bounds", "temperature:-40:100"]);
+        match process(&bounded, input.as_bytes(), None::<&[u8]>) {
+            Err(SensoryError::Parse(err)) => assert_eq!((err.line, err.message.as_str()), (3, "temperature 105 is outside the plausible range -40..100")),
+            other => panic!("expected a parse error, got {:?}", other.map(|day_summaries| day_summaries.0.len())),
+        }
+
+        let mut rejects = Rejects::default();
+        let day_summaries = process_with(&config(&["--bounds", "temperature:-40:100", "--report-errors"]), input.as_bytes(), None::<&[u8]>, &mut rejects).unwrap();
+        assert_eq!(rejects.lines, vec![3]);
+        assert_eq!(rejects.tally.out_of_range, 1);
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0]);
+        // The other metrics keep their default bounds.
+        assert_eq!(bounded.bounds(Metric::Humidity), Config::default().bounds(Metric::Humidity));
+    }
+
+    #[cfg(feature = "xlsx")]
+    #[test]
+    fn the_first_sheet_of_a_workbook_reads_as_its_csv_export() {
+        let fixtures = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+        let workbook = std::fs::read(fixtures.join("readings.xlsx")).unwrap();
+        let csv = std::fs::read_to_string(fixtures.join("readings.csv")).unwrap();
+        assert_eq!(String::from_utf8(read_xlsx(&workbook).unwrap()).unwrap(), csv);
+        assert!(read_xlsx(csv.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn compare_summaries_diffs_each_date_and_keeps_dates_in_only_one() {
+        let summary = "date,avg temp,gdd,event\n2021-01-01,60,5,\n2021-01-02,50,8,planted\n";
+        let other = "date,avg temp,gdd,event\n2021-01-02,55,8,planted\n2021-01-03,40,3,\n";
+        let diffs = compare_summaries(summary.as_bytes(), other.as_bytes()).unwrap();
+        let diff = |date: &str, metric: &str, value: Option<f32>, other: Option<f32>| SummaryDiff { date: date.to_string(), metric: metric.to_string(), value, other };
+        assert_eq!(diffs, vec![
+            diff("2021-01-01", "avg temp", Some(60.0), None),
+            diff("2021-01-01", "gdd", Some(5.0), None),
+            diff("2021-01-02", "avg temp", Some(50.0), Some(55.0)),
+            diff("2021-01-02", "gdd", Some(8.0), Some(8.0)),
+            diff("2021-01-03", "avg temp", None, Some(40.0)),
+            diff("2021-01-03", "gdd", None, Some(3.0)),
+        ]);
+
+        assert_eq!(diffs[2].difference(), Some(5.0));
+        assert_close(diffs[2].percent_difference().unwrap(), 10.0, DEFAULT_EPSILON);
+        assert_eq!(diffs[3].percent_difference(), Some(0.0));
+        assert_eq!((diffs[0].difference(), diffs[0].percent_difference()), (None, None));
+        assert_eq!(diffs[4].difference(), None);
+    }
+
+    #[test]
+    fn dew_point_spread_averages_temperature_minus_dew_point() {
+        // Spreads of 10, 0 (saturated) and 5.
+        let input = format!("{}2021-01-01 06:00:00,60,70,50,1.0\n2021-01-01 12:00:00,55,100,55,0.0\n2021-01-01 18:00:00,70,80,65,0.5\n", HEADER);
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        let spread = &day_summaries.0[0].dew_point_spread_stats;
+        assert_eq!(spread.entries, vec![10.0, 0.0, 5.0]);
+        assert_close(spread.mean, 5.0, DEFAULT_EPSILON);
+        assert_close(spread.min, 0.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn an_empty_bucket_has_no_mean_rather_than_nan() {
+        assert_eq!(safe_mean(0.0, 0), None);
+        assert_eq!(safe_mean(6.0, 4), Some(1.5));
+
+        // Every dew point reading is missing, so the dew point has an empty bucket.
+        let input = format!("{}2021-01-01 06:00:00,60,40,NA,1.0\n2021-01-01 18:00:00,70,60,NA,1.0\n", HEADER);
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert!(day_summaries.0[0].entries(Metric::DewPoint).is_empty());
+        assert_eq!(day_summaries.0[0].std_dev(Metric::DewPoint), None);
+        assert!(day_summaries.season_stats().iter().all(|season_stats| season_stats.metric != Metric::DewPoint));
+    }
+
+    #[test]
+    fn a_wind_speed_column_gives_a_wind_chill_adjusted_apparent_temperature() {
+        let input = "timestamp,temperature,humidity,dewpoint,vpd,wind speed\n2021-01-01 06:00:00,30,60,18,0.2,20\n2021-01-01 12:00:00,30,60,18,0.2,2\n";
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        let apparent = &day_summaries.0[0].apparent_temperature_stats;
+        // The NWS wind chill chart gives 17 °F for 30 °F in a 20 mph wind; a 2 mph breeze is calm.
+        assert_close(apparent.entries[0], 17.0, 0.5);
+        assert_close(apparent.entries[1], 30.0, DEFAULT_EPSILON);
+        assert_close(apparent.mean, (apparent.entries[0] + 30.0) / 2.0, DEFAULT_EPSILON);
+
+        let input = format!("{}2021-01-01 06:00:00,30,60,18,0.2\n", HEADER);
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert!(day_summaries.0[0].apparent_temperature_stats.entries.is_empty());
+    }
+
+    #[test]
+    fn tiny_values_are_formatted_in_fixed_point() {
+        assert_eq!(format_number(1e-7, None), "0.0000001");
+        assert_eq!(format_number(1e-7, Some(3)), "0.000");
+        assert_eq!(format_number(2.5e-4, Some(4)), "0.0003");
+        assert_eq!(format_number(1.5e10, None), "15000000000");
+    }
+
+    const SHUFFLED: &str = "2021-01-03 12:00:00,50,80,44,0.3\n2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 12:00:00,55,60,40,0.8\n2021-01-01 18:00:00,70,60,45,1.4\n";
+
+    #[test]
+    fn sorted_input_is_summarized_in_ascending_order() {
+        let input = format!("{}{}", HEADER, SHUFFLED);
+        let day_summaries = process(&config(&["--sort-input"]), input.as_bytes(), None::<&[u8]>).unwrap();
+
+        let dates: Vec<String> = day_summaries.iter().map(|day_summary| day_summary.date.to_string()).collect();
+        assert_eq!(dates, vec!["2021-01-01", "2021-01-02", "2021-01-03"]);
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn streamed_input_rejects_readings_for_completed_days() {
+        let input = format!("{}{}", HEADER, SHUFFLED);
+        match process(&Config::default(), input.as_bytes(), None::<&[u8]>) {
+            Err(SensoryError::Parse(err)) => {
+                assert_eq!(err.line, 3);
+                assert!(err.message.starts_with("out of order: 2021-01-01 06:00:00 is before 2021-01-03"), "{}", err.message);
+            },
+            other => panic!("expected an out of order error, got {:?}", other.map(|day_summaries| day_summaries.0.len())),
+        }
+
+        let day_summaries = process(&config(&["--report-errors"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        let dates: Vec<String> = day_summaries.iter().map(|day_summary| day_summary.date.to_string()).collect();
+        assert_eq!(dates, vec!["2021-01-03"]);
+    }
+
+    #[test]
+    fn fahrenheit_degree_days_convert_to_celsius_degree_days_by_the_degree_size() {
+        let fahrenheit = TemperatureScale::Fahrenheit;
+        assert_close(fahrenheit.degree_days_in(18.0, TemperatureScale::Celsius), 10.0, DEFAULT_EPSILON);
+        assert_close(TemperatureScale::Celsius.degree_days_in(10.0, TemperatureScale::Fahrenheit), 18.0, DEFAULT_EPSILON);
+        assert_close(fahrenheit.degree_days_in(18.0, TemperatureScale::Fahrenheit), 18.0, DEFAULT_EPSILON);
+        // Not a temperature conversion: 0 degree days stay 0 rather than becoming -17.8.
+        assert_close(fahrenheit.degree_days_in(0.0, TemperatureScale::Celsius), 0.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn integrated_vpd_is_the_trapezoidal_area_between_readings() {
+        // 1.0 kPa rising to 2.0 kPa over 4 hours encloses 6 kPa·hours.
+        let input = format!("{}2021-01-01 08:00:00,60,40,35,1.0\n2021-01-01 12:00:00,70,40,35,2.0\n2021-01-02 08:00:00,60,40,35,9.0\n", HEADER);
+        let day_summaries = process(&config(&["--integrated-vpd"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].vpd_hours, 6.0, DEFAULT_EPSILON);
+        // The overnight span to the next day's first reading belongs to neither day.
+        assert_close(day_summaries.0[1].vpd_hours, 0.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn clock_issues_detect_a_backwards_jump_and_a_stalled_timestamp() {
+        let input = format!(
+            "{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 07:00:00,61,40,35,1.0\n2021-01-01 06:30:00,62,40,35,1.0\n2021-01-01 06:30:00,63,40,35,1.0\n2021-01-01 08:00:00,64,40,35,1.0\n",
+            HEADER,
+        );
+        let issues = clock_issues(input.as_bytes(), &Config::default()).unwrap();
+        assert_eq!(issues, vec![
+            ClockIssue::Backwards { line: 4, previous: "2021-01-01 07:00:00".to_string(), timestamp: "2021-01-01 06:30:00".to_string() },
+            ClockIssue::Stalled { line: 5, timestamp: "2021-01-01 06:30:00".to_string() },
+        ]);
+        assert_eq!(issues[0].to_string(), "line 4: clock went back from 2021-01-01 07:00:00 to 2021-01-01 06:30:00");
+
+        let in_order = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 07:00:00,61,40,35,1.0\n", HEADER);
+        assert!(clock_issues(in_order.as_bytes(), &Config::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn approx_percentiles_estimate_the_median_without_keeping_readings() {
+        // A day of minutely readings, 0 to 49.9 in a scrambled order.
+        let mut input = HEADER.to_string();
+        for minute in 0..500 {
+            input += &format!("2021-01-01 {:02}:{:02}:00,{},50,40,1.0\n", minute / 60, minute % 60, (minute * 37 % 500) as f32 / 10.0);
+        }
+        let exact = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        let estimated = process(&config(&["--approx-percentiles"]), input.as_bytes(), None::<&[u8]>).unwrap();
+
+        let mut entries = exact.0[0].entries(Metric::Temperature).to_vec();
+        entries.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        let median = entries[entries.len() / 2];
+        assert_eq!(median, 25.0);
+        assert!(estimated.0[0].entries(Metric::Temperature).is_empty());
+        assert_close(estimated.0[0].value(Metric::Temperature, Aggregate::Median), median, 0.5);
+        assert_close(estimated.0[0].value(Metric::Temperature, Aggregate::Mean), exact.0[0].value(Metric::Temperature, Aggregate::Mean), DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn report_errors_skips_a_bad_row_and_processes_the_rest() {
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 12:00:00,hot,40,35,1.0\n2021-01-01 18:00:00,70,60,45,1.4\n2021-01-02 12:00:00,50,80,44,0.3\n", HEADER);
+        assert!(matches!(process(&Config::default(), input.as_bytes(), None::<&[u8]>), Err(SensoryError::Parse(_))));
+
+        let mut rejects = Rejects::default();
+        let day_summaries = process_with(&config(&["--report-errors"]), input.as_bytes(), None::<&[u8]>, &mut rejects).unwrap();
+        assert_eq!(rejects.lines, vec![3]);
+        assert_eq!((rejects.tally.processed, rejects.tally.parse_errors), (3, 1));
+        assert_eq!(day_summaries.0.len(), 2);
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn a_time_outside_the_day_is_a_bad_row() {
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 24:00:00,99,40,35,1.0\n2021-01-01 12:60:00,99,40,35,1.0\n2021-01-01 18:00:00,70,60,45,1.4\n", HEADER);
+        let mut rejects = Rejects::default();
+        let day_summaries = process_with(&config(&["--report-errors"]), input.as_bytes(), None::<&[u8]>, &mut rejects).unwrap();
+        assert_eq!(rejects.lines, vec![3, 4]);
+        assert_eq!(rejects.tally.parse_errors, 2);
+        assert_eq!(day_summaries.0.len(), 1);
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 70.0]);
+
+        // Far too large to be seconds of anything, let alone a day.
+        assert_eq!(parse_timestamp("2021-01-01 4294967295:00:00", None), None);
+        assert_eq!(parse_timestamp("2021-01-01 23:59:59", None), Some((NaiveDate::from_ymd(2021, 1, 1), 86399)));
+    }
+
+    #[test]
+    fn parse_options_set_the_na_values_date_format_and_columns() {
+        let record = csv::StringRecord::from(vec!["01/06/2021 12:00", "70", "50", "1.5", "--"]);
+        let options = ParseOptions {
+            na_values: vec!["--".to_string()],
+            date_format: Some("%d/%m/%Y %H:%M".to_string()),
+            dew_point_column: Some(4),
+            vpd_column: Some(3),
+        };
+        let sensor_record = SensorRecord::try_from_csv_record(&record, &options).unwrap();
+        assert_eq!((sensor_record.timestamp, sensor_record.seconds_of_day), (NaiveDate::from_ymd(2021, 6, 1), 43200));
+        assert_close(sensor_record.vpd, 1.5, DEFAULT_EPSILON);
+        assert!(sensor_record.dew_point.is_nan());
+
+        // Without the date format, the timestamp doesn't parse.
+        assert!(matches!(SensorRecord::try_from_csv_record(&record, &ParseOptions::default()), Err(SensoryError::Parse(_))));
+    }
+
+    #[test]
+    fn flooring_the_min_before_averaging_differs_from_flooring_the_result() {
+        // A warm afternoon of 70°F after a 20°F night, against a base of 50.
+        let input = format!("{}2021-04-01 05:00:00,20,50,10,0.3\n2021-04-01 15:00:00,70,50,50,1.0\n", HEADER);
+        let gdd = |method: &str| process(&config(&["--gdd-method", method, "--gdd-base", "50"]), input.as_bytes(), None::<&[u8]>).unwrap().0[0].gdd;
+
+        // (70 + 50) / 2 - 50: the night is raised to the base, so the afternoon still counts.
+        assert_close(gdd("base-floor"), 10.0, DEFAULT_EPSILON);
+        // max(0, (70 + 20) / 2 - 50): the night cancels out the afternoon.
+        assert_close(gdd("min-max"), 0.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn climatology_averages_each_day_of_the_year_across_years() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[
+            record("2020-02-29", 40.0, 60.0),
+            record("2020-03-01", 50.0, 60.0),
+            record("2021-03-01", 60.0, 40.0),
+            record("2021-12-31", 30.0, 80.0),
+        ] {
+            day_summaries.add_record(record, &config);
+        }
+
+        let climatology = day_summaries.climatology();
+        let days: Vec<(u32, usize)> = climatology.iter().map(|day| (day.day_of_year, day.years)).collect();
+        // March 1st is day 61 in both years, and December 31st day 366 even outside a leap year.
+        assert_eq!(days, vec![(60, 1), (61, 2), (366, 1)]);
+        assert_close(climatology[1].means[Metric::Temperature.index()], 55.0, DEFAULT_EPSILON);
+        assert_close(climatology[1].means[Metric::Humidity.index()], 50.0, DEFAULT_EPSILON);
+        assert_close(climatology[0].means[Metric::Temperature.index()], 40.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn events_in_their_own_date_format_still_attach_to_their_days() {
+        let input = "timestamp,temperature,humidity,dewpoint,vpd\n01.03.2021 06:00,60,40,35,1.0\n02.03.2021 06:00,62,40,35,1.0\n";
+        let events = "timestamp,event\n03/02/2021 08:00 PM,planted\n";
+        let sensor_format = config(&["--date-format", "%d.%m.%Y %H:%M"]);
+        // Read in the sensor data's format, the event's date doesn't parse.
+        assert!(matches!(process(&sensor_format, input.as_bytes(), Some(events.as_bytes())), Err(SensoryError::Events(_))));
+
+        let both_formats = config(&["--date-format", "%d.%m.%Y %H:%M", "--event-date-format", "%m/%d/%Y %I:%M %p"]);
+        let day_summaries = process(&both_formats, input.as_bytes(), Some(events.as_bytes())).unwrap();
+        let events: Vec<(String, Vec<String>)> = day_summaries.iter().map(|day| (day.date.to_string(), day.events.clone())).collect();
+        assert_eq!(events, vec![("2021-03-01".to_string(), vec![]), ("2021-03-02".to_string(), vec!["planted".to_string()])]);
+    }
+
+    #[test]
+    fn row_tally_display_separates_thousands() {
+        let tally = RowTally { processed: 9850, parse_errors: 120, out_of_range: 30, ..RowTally::default() };
+        assert_eq!(tally.to_string(), "Processed 9,850 of 10,000 rows; 150 skipped (120 parse errors, 30 out-of-range)");
+    }
+
+    #[test]
+    fn hourly_gdd_divides_degree_hours_by_a_day() {
+        // 70°F from 08:00 to 20:00 is 12 hours at 20 degrees above a base of 50: 240 degree-hours,
+        // or 10 gdd.
+        let mut input = HEADER.to_string();
+        for hour in 8..=20 {
+            input += &format!("2021-06-01 {:02}:00:00,70,50,50,1.0\n", hour);
+        }
+        let config = config(&["--gdd-method", "hourly", "--gdd-base", "50"]);
+        let day_summaries = process(&config, input.as_bytes(), None::<&[u8]>).unwrap();
+        let day = &day_summaries.0[0];
+        assert_close(day.degree_hours[0], 240.0, DEFAULT_EPSILON);
+        assert_close(day.degree_hours_span, 12.0, DEFAULT_EPSILON);
+        assert_close(day.gdd, 10.0, DEFAULT_EPSILON);
+
+        // A lone reading has nothing to integrate, so it falls back to min/max.
+        let input = format!("{}2021-06-01 12:00:00,70,50,50,1.0\n", HEADER);
+        let day_summaries = process(&config, input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].gdd, 20.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn a_flag_column_marks_the_days_it_was_ever_true() {
+        let input = "timestamp,temperature,humidity,dewpoint,vpd,rain\n\
+            2021-01-01 06:00:00,60,40,35,1.0,0\n2021-01-01 12:00:00,60,40,35,1.0,no\n2021-01-01 18:00:00,60,40,35,1.0,\n\
+            2021-01-02 06:00:00,60,40,35,1.0,0\n2021-01-02 12:00:00,60,40,35,1.0,Yes\n\
+            2021-01-03 06:00:00,60,40,35,1.0,TRUE\n\
+            2021-01-04 06:00:00,60,40,35,1.0,1\n";
+        let day_summaries = process(&config(&["--flag-column", "Rain"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        let flags: Vec<bool> = day_summaries.iter().map(|day| day.flag).collect();
+        assert_eq!(flags, vec![false, true, true, true]);
+
+        assert!(["1", "true", "T", "yes", " y "].iter().all(|value| is_truthy(value)));
+        assert!(!["0", "false", "no", "", "2"].iter().any(|value| is_truthy(value)));
+    }
+
+    // A reader that fails on the first read, like a file on a dropped network share.
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "connection reset"))
+        }
+    }
+
+    #[test]
+    fn process_errors_match_their_variants() {
+        let no_events = None::<&[u8]>;
+        assert!(matches!(process(&Config::default(), FailingReader, no_events), Err(SensoryError::Io(_))));
+
+        let short_row = format!("{}2021-01-01 06:00:00,60,40\n", HEADER);
+        assert!(matches!(process(&Config::default(), short_row.as_bytes(), no_events), Err(SensoryError::Csv(_))));
+
+        let bad_reading = format!("{}2021-01-01 06:00:00,60,forty,35,1.0\n", HEADER);
+        match process(&Config::default(), bad_reading.as_bytes(), no_events) {
+            Err(err @ SensoryError::Parse(_)) => {
+                assert_eq!(err.to_string(), "Error parsing sensor data: line 2: invalid humidity: \"forty\"");
+                assert!(std::error::Error::source(&err).is_some());
+            },
+            other => panic!("expected a parse error, got {:?}", other.map(|day_summaries| day_summaries.0.len())),
+        }
+
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n", HEADER);
+        let bad_event = "timestamp,event\nlast tuesday,planted\n";
+        assert!(matches!(process(&Config::default(), input.as_bytes(), Some(bad_event.as_bytes())), Err(SensoryError::Events(_))));
+        assert!(matches!(process(&config(&["--flag-column", "irrigated"]), input.as_bytes(), no_events), Err(SensoryError::Schema(_))));
+    }
+
+    #[test]
+    fn days_complete_as_the_input_is_read() {
+        struct CompletedDays(usize);
+
+        impl ProcessObserver for CompletedDays {
+            fn on_day_complete(&mut self, _day_summary: &DaySummaryStats<NaiveDate>) -> Result<(), SensoryError> {
+                self.0 += 1;
+                Ok(())
+            }
+        }
+
+        let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n2021-01-03 06:00:00,64,40,35,1.0\n", HEADER);
+        let mut completed_days = CompletedDays(0);
+        let result = process_with(&Config::default(), input.as_bytes().chain(FailingReader), None::<&[u8]>, &mut completed_days);
+        assert!(matches!(result, Err(SensoryError::Io(_))));
+        assert_eq!(completed_days.0, 2);
+
+        // A multibyte character cut in half ends the final record, which is only dropped when
+        // tolerating truncation.
+        let mut truncated = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0,°", HEADER).into_bytes();
+        truncated.pop();
+        assert!(matches!(process(&Config::default(), truncated.as_slice(), None::<&[u8]>), Err(SensoryError::Csv(_))));
+        let day_summaries = process(&config(&["--tolerate-truncation"]), truncated.as_slice(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0.len(), 1);
+    }
+
+    #[test]
+    fn readings_flagged_bad_are_left_out_of_the_day_mean() {
+        let input = "timestamp,temperature,humidity,dewpoint,vpd,status\n\
+            2021-01-01 06:00:00,60,40,35,1.0,OK\n2021-01-01 12:00:00,140,40,35,1.0,BAD\n2021-01-01 18:00:00,70,60,45,1.4,good\n";
+        let mut rejects = Rejects::default();
+        let day_summaries = process_with(&config(&["--quality-column", "status"]), input.as_bytes(), None::<&[u8]>, &mut rejects).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+        // Still counted among the rows read.
+        assert_eq!((rejects.tally.processed, rejects.tally.flagged_bad, rejects.tally.rows()), (2, 1, 3));
+
+        let day_summaries = process(&config(&["--quality-column", "status", "--good-quality", "OK,BAD"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 140.0]);
+    }
+
+    #[test]
+    fn pre_aggregating_to_hours_weights_each_hour_equally() {
+        // Six readings in the first hour and just one in the second.
+        let mut input = HEADER.to_string();
+        for minute in (0..60).step_by(10) {
+            input += &format!("2021-01-01 06:{:02}:00,60,40,35,1.0\n", minute);
+        }
+        input += "2021-01-01 07:00:00,90,40,35,1.0\n";
+
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 450.0 / 7.0, DEFAULT_EPSILON);
+
+        let day_summaries = process(&config(&["--pre-aggregate", "1h"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::Temperature, Aggregate::Mean), 75.0, DEFAULT_EPSILON);
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0, 90.0]);
+    }
+
+    // Collects the warnings of a read.
+    #[derive(Default)]
+    struct Warnings(Vec<String>);
+
+    impl ProcessObserver for Warnings {
+        fn on_warning(&mut self, warning: &str) {
+            self.0.push(warning.to_string());
+        }
+    }
+
+    #[test]
+    fn humidity_pinned_at_100_percent_is_flagged_as_saturation() {
+        let input = format!(
+            "{}2021-01-01 06:00:00,50,100,50,0.0\n2021-01-01 07:00:00,51,100,51,0.0\n2021-01-01 08:00:00,52,100,52,0.0\n2021-01-01 12:00:00,60,80,54,0.4\n2021-01-02 06:00:00,55,99.9,55,0.0\n",
+            HEADER,
+        );
+        let mut warnings = Warnings::default();
+        let day_summaries = process_with(&config(&["--rails", "humidity:0:100"]), input.as_bytes(), None::<&[u8]>, &mut warnings).unwrap();
+        let rail_readings: Vec<usize> = day_summaries.iter().map(|day| day.rail_readings).collect();
+        assert_eq!(rail_readings, vec![3, 0]);
+        assert_eq!(warnings.0, vec!["3 readings on 1 days sat exactly at a rail value; the sensor may be saturating"]);
+
+        // In range, so without rails nothing is flagged.
+        let mut warnings = Warnings::default();
+        let day_summaries = process_with(&Config::default(), input.as_bytes(), None::<&[u8]>, &mut warnings).unwrap();
+        assert!(day_summaries.iter().all(|day| day.rail_readings == 0));
+        assert!(warnings.0.is_empty());
+    }
+
+    #[test]
+    fn day_summaries_iterate_in_date_order() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[record("2021-01-01", 60.0, 40.0), record("2021-01-02", 62.0, 40.0), record("2021-01-04", 64.0, 40.0)] {
+            day_summaries.add_record(record, &config);
+        }
+
+        let mut dates = Vec::new();
+        for day_summary_stats in &day_summaries {
+            dates.push(day_summary_stats.date.to_string());
+        }
+        assert_eq!(dates, vec!["2021-01-01", "2021-01-02", "2021-01-04"]);
+        assert_eq!(day_summaries.iter().map(|day_summary_stats| day_summary_stats.date.to_string()).collect::<Vec<_>>(), dates);
+    }
+
+
+    // Readings for the percentile method tests, sorted.
+    const PERCENTILE_READINGS: [f32; 5] = [15.0, 20.0, 35.0, 40.0, 50.0];
+
+    #[test]
+    fn nearest_rank_percentile_takes_the_reading_at_the_rank() {
+        // ceil(0.4 · 5) = 2 and ceil(0.9 · 5) = 5.
+        assert_eq!(PercentileMethod::Nearest.percentile(&PERCENTILE_READINGS, 0.4), 20.0);
+        assert_eq!(PercentileMethod::Nearest.percentile(&PERCENTILE_READINGS, 0.9), 50.0);
+        assert_eq!(PercentileMethod::Nearest.percentile(&PERCENTILE_READINGS, 0.0), 15.0);
+    }
+
+    #[test]
+    fn linear_percentile_interpolates_at_rank_n_minus_one_times_p_plus_one() {
+        // Ranks 2.6 and 4.6.
+        assert_close(PercentileMethod::Linear.percentile(&PERCENTILE_READINGS, 0.4), 29.0, DEFAULT_EPSILON);
+        assert_close(PercentileMethod::Linear.percentile(&PERCENTILE_READINGS, 0.9), 46.0, DEFAULT_EPSILON);
+        assert_close(PercentileMethod::Linear.percentile(&PERCENTILE_READINGS, 1.0), 50.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn hazen_percentile_interpolates_at_rank_n_times_p_plus_a_half() {
+        // Rank 2.5, and 5.0 for the 90th, which is clamped to the last reading.
+        assert_close(PercentileMethod::Hazen.percentile(&PERCENTILE_READINGS, 0.4), 27.5, DEFAULT_EPSILON);
+        assert_close(PercentileMethod::Hazen.percentile(&PERCENTILE_READINGS, 0.9), 50.0, DEFAULT_EPSILON);
+        assert_close(PercentileMethod::Hazen.percentile(&PERCENTILE_READINGS, 0.05), 15.0, DEFAULT_EPSILON);
+
+        let input: String = PERCENTILE_READINGS.iter().enumerate()
+            .map(|(hour, temperature)| format!("2021-01-01 {:02}:00:00,{},40,35,1.0\n", hour, temperature))
+            .collect();
+        let config = config(&["--percentile", "40", "--percentile-method", "hazen"]);
+        let day_summaries = process(&config, format!("{}{}", HEADER, input).as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].stats(Metric::Temperature).percentile(0.4, config.percentile_method), 27.5, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn the_sorted_readings_are_kept_until_another_is_added() {
+        let mut stats = MetricStats::from_value(30.0);
+        stats.add(10.0);
+        stats.add(20.0);
+        assert_eq!(stats.median(), 20.0);
+        assert_eq!(stats.percentile(1.0, PercentileMethod::Nearest), 30.0);
+
+        stats.add(40.0);
+        assert_eq!(stats.median(), 25.0);
+        assert_eq!(stats.percentile(1.0, PercentileMethod::Nearest), 40.0);
+        // The readings themselves stay in the order they were read.
+        assert_eq!(stats.entries, vec![30.0, 10.0, 20.0, 40.0]);
+    }
+
+    #[test]
+    fn report_has_frost_and_gap_sections() {
+        let dataset = SensorDataset(vec![
+            record("2021-04-01", 30.0, 50.0),
+            record("2021-04-02", 45.0, 50.0),
+            record("2021-04-05", 50.0, 50.0),
+            record("2021-10-20", 28.0, 50.0),
+        ]);
+        let report = analyze(&dataset, &config(&["--frost-dates"]));
+
+        let date = |date: &str| date.parse::<NaiveDate>().unwrap();
+        assert_eq!(report.days, 4);
+        assert_eq!(report.gaps, vec![(date("2021-04-03"), date("2021-04-04")), (date("2021-04-06"), date("2021-10-19"))]);
+        let frost_seasons = report.frost_seasons.as_ref().unwrap();
+        assert_eq!(frost_seasons.len(), 1);
+        assert_eq!(frost_seasons[0].last_spring_frost, Some(date("2021-04-01")));
+        assert_eq!(frost_seasons[0].first_fall_frost, Some(date("2021-10-20")));
+        assert!(report.season_stats.is_none());
+        assert!(report.to_json().contains("\"first_fall_frost\":\"2021-10-20\""));
+    }
+
+    #[test]
+    fn exact_medians_from_a_second_pass_match_the_in_memory_ones() {
+        // Two days of readings in a scrambled order, with an even count on the second.
+        let mut input = HEADER.to_string();
+        for (day, readings) in [(1, 301), (2, 200)] {
+            for reading in 0..readings {
+                input += &format!("2021-01-0{} {:02}:{:02}:00,{},{},40,1.0\n", day, reading / 60, reading % 60, (reading * 37 % readings) as f32 / 4.0, (reading * 11 % readings) as f32 / 8.0);
+            }
+        }
+        let in_memory = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        let config = config(&["--approx-percentiles", "--exact-medians", "--percentile", "90"]);
+        let mut two_pass = process(&config, input.as_bytes(), None::<&[u8]>).unwrap();
+        assert!(two_pass.iter().all(|day_summary| day_summary.stats(Metric::Temperature).entries.is_empty()));
+        let estimated_percentile = two_pass.0[0].stats(Metric::Temperature).percentile(0.9, config.percentile_method);
+        apply_exact_medians(&config, input.as_bytes(), &mut two_pass, None).unwrap();
+
+        for (exact, recomputed) in in_memory.iter().zip(&two_pass) {
+            for &metric in &[Metric::Temperature, Metric::Humidity] {
+                assert_eq!(recomputed.value(metric, Aggregate::Median), exact.value(metric, Aggregate::Median));
+                assert_eq!(recomputed.stats(metric).percentile(0.9, config.percentile_method), exact.stats(metric).percentile(0.9, config.percentile_method));
+            }
+        }
+        assert_ne!(two_pass.0[0].stats(Metric::Temperature).percentile(0.9, config.percentile_method), estimated_percentile);
+        assert_close(two_pass.0[0].value(Metric::Temperature, Aggregate::Median), 37.5, DEFAULT_EPSILON);
+        assert_close(two_pass.0[1].value(Metric::Temperature, Aggregate::Median), 24.875, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn absent_dew_point_and_vpd_are_derived_from_temperature_and_humidity() {
+        let expected_dew_point = TemperatureScale::Fahrenheit.convert_celsius(dew_point(TemperatureScale::Fahrenheit.to_celsius(70.0), 50.0));
+        let expected_vpd = vapor_pressure_deficit(TemperatureScale::Fahrenheit.to_celsius(70.0), 50.0);
+        for (header, row) in &[
+            ("timestamp,temperature,humidity", "2021-06-01 12:00:00,70,50"),
+            ("timestamp,temperature,humidity,wind speed,rain", "2021-06-01 12:00:00,70,50,4,0.2"),
+        ] {
+            let input = format!("{}\n{}\n", header, row);
+            let mut warnings = Warnings::default();
+            let day_summaries = process_with(&Config::default(), input.as_bytes(), None::<&[u8]>, &mut warnings).unwrap();
+            let day = &day_summaries.0[0];
+            assert_close(day.value(Metric::Temperature, Aggregate::Mean), 70.0, DEFAULT_EPSILON);
+            assert_close(day.value(Metric::Humidity, Aggregate::Mean), 50.0, DEFAULT_EPSILON);
+            assert_close(day.value(Metric::DewPoint, Aggregate::Mean), expected_dew_point, DEFAULT_EPSILON);
+            assert_close(day.value(Metric::Vpd, Aggregate::Mean), expected_vpd, DEFAULT_EPSILON);
+            assert!(warnings.0.contains(&"the sensor data has no dew point or vpd columns; both were derived from temperature and humidity".to_string()), "{}: {:?}", header, warnings.0);
+        }
+        assert_close(expected_dew_point, 50.5, 0.1);
+
+        // Columns are found by name, so a vpd column is read even without a dew point before it.
+        let input = "timestamp,temperature,humidity,vpd\n2021-06-01 12:00:00,70,50,1.5\n";
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::Vpd, Aggregate::Mean), 1.5, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[0].value(Metric::DewPoint, Aggregate::Mean), expected_dew_point, DEFAULT_EPSILON);
+
+        // With all five columns, one named some other way is still read from its usual place.
+        let input = "timestamp,temperature,humidity,Td (°F),VPD\n2021-06-01 12:00:00,70,50,48,1.5\n";
+        let mut warnings = Warnings::default();
+        let day_summaries = process_with(&Config::default(), input.as_bytes(), None::<&[u8]>, &mut warnings).unwrap();
+        assert_close(day_summaries.0[0].value(Metric::DewPoint, Aggregate::Mean), 48.0, DEFAULT_EPSILON);
+        assert_close(day_summaries.0[0].value(Metric::Vpd, Aggregate::Mean), 1.5, DEFAULT_EPSILON);
+        assert!(warnings.0.is_empty(), "{:?}", warnings.0);
+    }
+
+    #[test]
+    fn assert_close_accepts_values_within_epsilon() {
+        assert_close(0.1 + 0.2, 0.3, DEFAULT_EPSILON);
+        assert_close(65.0, 65.00005, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not within")]
+    fn assert_close_rejects_values_outside_epsilon() {
+        assert_close(65.0, 65.001, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn between_hours_keeps_only_the_readings_in_the_window() {
+        let input = format!(
+            "{}2021-01-01 02:00:00,50,80,45,0.2\n2021-01-01 08:00:00,70,50,50,1.0\n2021-01-01 14:00:00,80,40,52,1.6\n2021-01-01 20:00:00,60,60,46,0.6\n2021-01-01 23:00:00,52,75,44,0.3\n",
+            HEADER,
+        );
+        let all_day = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_close(all_day.0[0].value(Metric::Temperature, Aggregate::Mean), 62.4, DEFAULT_EPSILON);
+
+        // The window includes its start and leaves out its end.
+        let daytime = process(&config(&["--between-hours", "08:00-20:00"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(daytime.0[0].entries(Metric::Temperature), &[70.0, 80.0]);
+        assert_close(daytime.0[0].value(Metric::Temperature, Aggregate::Mean), 75.0, DEFAULT_EPSILON);
+
+        let night = process(&config(&["--between-hours", "20-08"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(night.0[0].entries(Metric::Temperature), &[50.0, 60.0, 52.0]);
+    }
+
+    #[test]
+    fn the_corn_preset_sets_the_base_and_the_modified_method_caps() {
+        let corn = config(&["--crop", "corn", "--gdd-method", "modified"]);
+        assert_eq!(corn.gdd_base(), 50.0);
+        assert_eq!(corn.degree_day_caps(), (Some(50.0), Some(86.0)));
+
+        // A 95°F afternoon and 40°F night are clamped to 86 and 50: (86 + 50) / 2 - 50.
+        let input = format!("{}2021-07-01 05:00:00,40,80,35,0.2\n2021-07-01 15:00:00,95,40,65,3.0\n", HEADER);
+        assert_close(process(&corn, input.as_bytes(), None::<&[u8]>).unwrap().0[0].gdd, 18.0, DEFAULT_EPSILON);
+
+        // An explicit cap overrides the preset's, and the preset fills in the rest.
+        let capped = config(&["--crop", "corn", "--gdd-method", "modified", "--degree-day-cap-high", "90"]);
+        assert_eq!(capped.degree_day_caps(), (Some(50.0), Some(90.0)));
+        assert_close(process(&capped, input.as_bytes(), None::<&[u8]>).unwrap().0[0].gdd, 20.0, DEFAULT_EPSILON);
+
+        // The preset is in Fahrenheit, and converted for Celsius data.
+        assert_eq!(config(&["--crop", "corn", "--celsius"]).degree_day_caps(), (Some(10.0), Some(30.0)));
+    }
+
+    #[test]
+    fn resampling_a_gapped_series_fills_every_missing_date() {
+        let config = Config::default();
+        let mut day_summaries = DaySummaries(Vec::new());
+        for record in &[record("2021-01-01", 60.0, 40.0), record("2021-01-04", 72.0, 70.0), record("2021-01-05", 50.0, 50.0)] {
+            day_summaries.add_record(record, &config);
+        }
+
+        let means = |resampled: &DaySummaries<NaiveDate>| resampled.series(Metric::Temperature, Aggregate::Mean).into_iter().map(|(_, mean)| mean).collect::<Vec<f32>>();
+        let interpolated = day_summaries.resample_daily(FillPolicy::Interpolate);
+        let dates: Vec<String> = interpolated.iter().map(|day| day.date.to_string()).collect();
+        assert_eq!(dates, vec!["2021-01-01", "2021-01-02", "2021-01-03", "2021-01-04", "2021-01-05"]);
+        assert_eq!(interpolated.iter().map(|day| day.interpolated).collect::<Vec<bool>>(), vec![false, true, true, false, false]);
+        assert_eq!(means(&interpolated), vec![60.0, 64.0, 68.0, 72.0, 50.0]);
+        assert_close(interpolated.0[1].value(Metric::Humidity, Aggregate::Mean), 50.0, DEFAULT_EPSILON);
+
+        assert_eq!(means(&day_summaries.resample_daily(FillPolicy::Forward)), vec![60.0, 60.0, 60.0, 72.0, 50.0]);
+        let unfilled = day_summaries.resample_daily(FillPolicy::None);
+        assert_eq!(unfilled.0.len(), 5);
+        assert!(unfilled.0[1].value(Metric::Temperature, Aggregate::Mean).is_nan());
+        // The original series is left as it was.
+        assert_eq!(day_summaries.0.len(), 3);
+    }
+
+    #[test]
+    fn mixed_line_endings_and_a_stray_carriage_return_parse_cleanly() {
+        let input = "timestamp,temperature,humidity,dewpoint,vpd\r\n\
+            2021-01-01 06:00:00,60,40,35,1.0\r\n\
+            2021-01-01 12:00:00,62,40,35,1.1\n\
+            2021-01-01 18:00:00,64,40,35,\"1.2\r\"\r\n\
+            2021-01-02 06:00:00,66,40,35,1.3";
+        let day_summaries = process(&Config::default(), input.as_bytes(), None::<&[u8]>).unwrap();
+        assert_eq!(day_summaries.0.len(), 2);
+        assert_eq!(day_summaries.0[0].entries(Metric::Vpd), &[1.0, 1.1, 1.2]);
+        assert_eq!(day_summaries.0[1].entries(Metric::Vpd), &[1.3]);
+    }
+
+    // A custom metric for the tests: twice the reading in a column.
+    #[derive(Debug)]
+    struct DoubledColumn(usize);
+
+    impl SummaryMetric for DoubledColumn {
+        fn name(&self) -> &str {
+            "doubled"
+        }
+
+        fn unit(&self) -> &str {
+            "units"
+        }
+
+        fn extract(&self, row: &csv::StringRecord) -> Option<f32> {
+            row.get(self.0)?.parse::<f32>().ok().map(|value| value * 2.0)
+        }
+    }
+
+    #[test]
+    fn registered_custom_metrics_are_summarized_after_the_builtin_ones() {
+        let mut config = Config::default();
+        config.metrics.push(Arc::new(DoubledColumn(5)));
+        let metrics = config.summary_metrics();
+        let names: Vec<&str> = metrics.iter().map(|metric| metric.name()).collect();
+        let units: Vec<&str> = metrics.iter().map(|metric| metric.unit()).collect();
+        assert_eq!(names, vec!["temp", "humidity", "dewpoint", "vpd", "doubled"]);
+        assert_eq!(units, vec!["°F", "%", "°F", "kPa", "units"]);
+
+        let input = "timestamp,temperature,humidity,dewpoint,vpd,light\n2021-01-01 06:00:00,60,40,35,1.0,10\n2021-01-01 12:00:00,70,40,35,1.0,30\n2021-01-01 18:00:00,65,40,35,1.0,20\n";
+        let day_summaries = process(&config, input.as_bytes(), None::<&[u8]>).unwrap();
+        let day = &day_summaries.0[0];
+        assert_eq!(day.metric_stats.len(), 5);
+        let doubled = &day.custom_stats()[0];
+        assert_close(doubled.mean, 40.0, DEFAULT_EPSILON);
+        assert_close(doubled.max, 60.0, DEFAULT_EPSILON);
+        assert_close(doubled.min, 20.0, DEFAULT_EPSILON);
+        assert_close(day.value(Metric::Temperature, Aggregate::Mean), 65.0, DEFAULT_EPSILON);
+    }
+
+    #[test]
+    fn streaming_detects_a_row_earlier_than_the_current_day() {
+        // Earlier within the day being summarized is fine; only a completed day can't take more.
+        let same_day = format!("{}2021-01-02 12:00:00,60,40,35,1.0\n2021-01-02 06:00:00,70,40,35,1.0\n", HEADER);
+        assert_eq!(process(&Config::default(), same_day.as_bytes(), None::<&[u8]>).unwrap().0[0].entries(Metric::Temperature), &[60.0, 70.0]);
+
+        let earlier_day = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n2021-01-01 18:00:00,99,40,35,1.0\n2021-01-02 18:00:00,64,40,35,1.0\n", HEADER);
+        let mut rejects = Rejects::default();
+        let day_summaries = process_with(&config(&["--report-errors"]), earlier_day.as_bytes(), None::<&[u8]>, &mut rejects).unwrap();
+        assert_eq!(rejects.lines, vec![4]);
+        assert_eq!(rejects.tally.out_of_order, 1);
+        // The completed day isn't touched by the late reading.
+        assert_eq!(day_summaries.0[0].entries(Metric::Temperature), &[60.0]);
+        assert_eq!(day_summaries.0[1].entries(Metric::Temperature), &[62.0, 64.0]);
+    }
+
+    #[test]
+    fn approx_percentiles_estimate_the_requested_percentile() {
+        // A day of minutely readings, 0 to 49.9 in a scrambled order.
+        let mut input = HEADER.to_string();
+        for minute in 0..500 {
+            input += &format!("2021-01-01 {:02}:{:02}:00,{},50,40,1.0\n", minute / 60, minute % 60, (minute * 37 % 500) as f32 / 10.0);
+        }
+        let exact = process(&config(&["--percentile", "90"]), input.as_bytes(), None::<&[u8]>).unwrap();
+        let estimated = process(&config(&["--approx-percentiles", "--percentile", "90"]), input.as_bytes(), None::<&[u8]>).unwrap();
+
+        let exact = exact.0[0].stats(Metric::Temperature);
+        let estimated = estimated.0[0].stats(Metric::Temperature);
+        assert!(estimated.entries.is_empty());
+        assert_close(exact.percentile(0.9, PercentileMethod::default()), 44.91, 0.01);
+        assert_close(estimated.percentile(0.9, PercentileMethod::default()), exact.percentile(0.9, PercentileMethod::default()), 0.5);
+        assert!(estimated.percentile(0.5, PercentileMethod::default()).is_nan());
+    }
+}
@@ -1,386 +1,974 @@
 use chrono::{NaiveDate};
-use std::collections::{HashMap};
+use sensor_data::{analyze_summaries, apply_exact_medians, clock_issues, compare_summaries, daily_files, process_group, Generator, format_number, group_labels, open_input, read_calibration, read_events_with_format, read_baseline, read_units_row, wind_speed_column, Aggregate, ClockIssue, Config, DaySummaryStats, day_of_leap_year, HourOfDayProfile, Metric, OutputFormat, ProcessObserver, Report, RowError, RowTally, SensorRecord, SensoryError, TemperatureScale};
+use std::collections::HashMap;
+use std::env;
 use std::fmt;
 use std::fs;
-
-#[derive(Debug)]
-struct SensorRecord<T> {
-    pub timestamp: T,
-    pub temperature: f32,
-    pub humidity: f32,
-    pub dew_point: f32,
-    pub vpd: f32,
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// How often --watch checks the input for changes.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Set by the first Ctrl-C, which ends the read early so that the completed days are still
+// written and checkpointed.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+// The exit status of a run that was interrupted (128 + SIGINT, as the shell reports it).
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+// A single output value, kept typed so that each output format can render it appropriately.
+#[derive(Clone)]
+enum Cell {
+    Text(String),
+    Number(f32),
+    Flag(bool),
+    Missing,
 }
 
-impl SensorRecord<NaiveDate> {
-    fn from_csv_record(record: csv::StringRecord) -> Self {
-        fn parse_date_time(datetime: &str) -> NaiveDate {
-            let date_parts: Vec<&str> = datetime.split(" ").collect();
-            let date_vec: Vec<&str> = date_parts[0].split("-").collect();
-
-            return NaiveDate::from_ymd(
-                date_vec[0].parse::<i32>().unwrap(),
-                date_vec[1].parse::<u32>().unwrap(),
-                date_vec[2].parse::<u32>().unwrap(),
-            );
-        }
-
-        return SensorRecord {
-            timestamp: parse_date_time(&record[0]),
-            temperature: record[1].parse::<f32>().unwrap(),
-            humidity: record[2].parse::<f32>().unwrap(),
-            dew_point: record[3].parse::<f32>().unwrap(),
-            vpd: record[4].parse::<f32>().unwrap(),
-        };
+impl Cell {
+    // The cell as csv text, with numbers rounded to `round` digits when given.
+    fn to_csv(&self, round: Option<usize>) -> String {
+        match self {
+            Cell::Text(text) => text.clone(),
+            // A metric without readings has no mean (or other aggregate); leave the cell empty
+            // rather than writing NaN.
+            Cell::Number(number) if number.is_finite() => format_number(*number, round),
+            Cell::Number(_) => String::new(),
+            Cell::Flag(flag) => flag.to_string(),
+            Cell::Missing => String::new(),
+        }
     }
-}
 
-struct DaySummaries<T>(Vec<DaySummaryStats<T>>);
+    fn to_json(&self, round: Option<usize>) -> String {
+        match self {
+            Cell::Text(text) => {
+                let mut escaped = String::from("\"");
+                for character in text.chars() {
+                    match character {
+                        '"' => escaped += "\\\"",
+                        '\\' => escaped += "\\\\",
+                        '\n' => escaped += "\\n",
+                        '\r' => escaped += "\\r",
+                        '\t' => escaped += "\\t",
+                        character if (character as u32) < 0x20 => escaped += format!("\\u{:04x}", character as u32).as_str(),
+                        character => escaped.push(character),
+                    }
+                }
+                escaped + "\""
+            },
+            Cell::Number(number) if number.is_finite() => format_number(*number, round),
+            Cell::Number(_) => "null".to_string(),
+            Cell::Flag(flag) => flag.to_string(),
+            Cell::Missing => "null".to_string(),
+        }
+    }
+}
 
-impl fmt::Display for DaySummaries<NaiveDate> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut output = String::new();
-        if let Some(first_day_summary) = self.0.first() {
-            if let Some(last_day_summary) = self.0.last() {
-                output += format!("{} records for date range {} - {}", self.0.len(), first_day_summary.date, last_day_summary.date).as_str();
-            }
-        } else {
-            output += format!("length: {}, date range: <na> - <na>", self.0.len()).as_str();
+// The row of a run of days collapsed by --collapse-stable, from the days' rows in order. The
+// cumulative columns (the gdd and degree day totals, the ewma) are as of the run's last day; the
+// others are aggregated over the run: the highest max, the lowest min, whether a flag was ever
+// set, the total rail readings, and the mean of any other daily value, such as the means.
+fn collapse_rows(header: &[String], rows: &[Vec<Cell>]) -> Vec<Cell> {
+    let last = &rows[rows.len() - 1];
+    header.iter().enumerate().map(|(column, name)| {
+        let cumulative = name == "gdd" || name.starts_with("gdd ") || name == "degree days" || name == "ewma temp";
+        let values: Vec<f32> = rows.iter().filter_map(|row| match row[column] {
+            Cell::Number(number) if number.is_finite() => Some(number),
+            _ => None,
+        }).collect();
+        match &last[column] {
+            Cell::Flag(_) => Cell::Flag(rows.iter().any(|row| matches!(row[column], Cell::Flag(true)))),
+            Cell::Number(_) | Cell::Missing if !cumulative => match values.len() {
+                0 => last[column].clone(),
+                _ if name.starts_with("max ") => Cell::Number(values.iter().copied().fold(f32::NEG_INFINITY, f32::max)),
+                _ if name.starts_with("min ") => Cell::Number(values.iter().copied().fold(f32::INFINITY, f32::min)),
+                _ if name == "rail readings" => Cell::Number(values.iter().sum()),
+                count => Cell::Number(values.iter().sum::<f32>() / count as f32),
+            },
+            cell => cell.clone(),
         }
+    }).collect()
+}
 
-        write!(f, "{}", output)
-    }
+// The header of the per-day flag column, e.g. `any rain`.
+fn flag_header(column: &str) -> String {
+    format!("any {}", column.trim())
 }
 
-impl fmt::Display for DaySummaryStats<NaiveDate> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}\n{}\n{}\n{}\n{}\ngdd: {}\n",
-            self.date,
-            self.temperature_stats,
-            self.humidity_stats,
-            self.dew_point_stats,
-            self.vpd_stats,
-            self.gdd,
-        )
-    }
+// Builds output rows from day summaries in chronological order, tracking the cumulative columns.
+struct RowBuilder<'a> {
+    config: &'a Config,
+    apparent_temperature: bool, // whether the input has a wind speed column to derive it from.
+    normalization: Vec<Option<(f32, f32)>>, // with --normalize, each metric's mean and std dev of its daily means.
+    total_gdd: f32,
+    total_gdd_by_base: Vec<f32>, // with several gdd bases, the running total against each of them.
+    total_degree_days: f32,
+    ewma_temperature: Option<f32>, // starts at the first day's mean temperature.
+    baseline: HashMap<u32, Vec<f32>>, // with --baseline, each day of the year's normals, in `Metric::ALL` order.
 }
 
-impl DaySummaries<NaiveDate> {
-    // Assumes Records are pre-sorted in a chronologically ascending order.
-    fn add_record(&mut self, record: &SensorRecord<NaiveDate>) {
-        match self.0.last_mut() {
-            Some(day_summary_stats) => {
-                if day_summary_stats.date == record.timestamp {
-                    day_summary_stats.calc_temperature_stats(record);
-                    day_summary_stats.calc_humidity_stats(record);
-                    day_summary_stats.calc_dew_point_stats(record);
-                    day_summary_stats.calc_vpd_stats(record);
-                    day_summary_stats.calc_growing_degrees_day();
-                } else {
-                    self.0.push(DaySummaryStats::from_record(record));
+impl<'a> RowBuilder<'a> {
+    fn new(config: &'a Config, apparent_temperature: bool) -> Self {
+        RowBuilder { config, apparent_temperature, normalization: Vec::new(), total_gdd: 0.0, total_gdd_by_base: vec![0.0; config.gdd_bases().len()], total_degree_days: 0.0, ewma_temperature: None, baseline: HashMap::new() }
+    }
+
+    // The day's mean of a metric, as a z-score when normalizing. A metric that never varies has
+    // no z-score.
+    fn mean_cell(&self, day_summary: &DaySummaryStats<NaiveDate>, metric: Metric) -> Cell {
+        let mean = day_summary.value(metric, Aggregate::Mean);
+        match self.normalization.get(metric.index()) {
+            Some(Some((dataset_mean, std_dev))) if *std_dev > 0.0 => Cell::Number((mean - dataset_mean) / std_dev),
+            Some(_) => Cell::Missing,
+            None => Cell::Number(mean),
+        }
+    }
+
+    fn header(&self) -> Vec<String> {
+        // Columns with a value per built-in metric are named after each one, e.g. `temp cv`.
+        let metrics = self.config.summary_metrics();
+        let (builtin_metrics, custom_metrics) = metrics.split_at(Metric::ALL.len());
+        let per_metric = |column: &dyn Fn(&str) -> String| builtin_metrics.iter().map(|metric| column(metric.name())).collect::<Vec<String>>();
+
+        // Every built-in metric's mean (a z-score when normalizing), with the max and min of the
+        // temperature and humidity, and then the cumulative gdd.
+        let mut header = vec!["date".to_string()];
+        for (metric, builtin_metric) in Metric::ALL.iter().zip(builtin_metrics) {
+            header.push(format!("avg {}{}", builtin_metric.name(), if self.config.normalize { " z" } else { "" }));
+            if matches!(metric, Metric::Temperature | Metric::Humidity) {
+                header.extend(["max", "min"].iter().map(|aggregate| format!("{} {}", aggregate, builtin_metric.name())));
+            }
+        }
+        header.push("gdd".to_string());
+        if self.config.freeze_column {
+            header.push("freeze".to_string());
+        }
+        if self.config.degree_day_base.is_some() {
+            header.push("degree days".to_string());
+        }
+        if self.config.gdd_both_units {
+            header.extend(["gdd f-days", "gdd c-days"].map(String::from));
+        }
+        if self.config.show_raw_gdd {
+            header.extend(["raw gdd", "gdd contribution"].map(String::from));
+        }
+        if self.config.gdd_settings_columns {
+            header.extend(["gdd method", "gdd base"].map(String::from));
+        }
+        if self.config.gdd_target.is_some() {
+            header.push("gdd % of target".to_string());
+        }
+        if self.config.expected_interval.is_some() {
+            header.push("completeness".to_string());
+        }
+        if self.config.interpolate_days {
+            header.push("interpolated".to_string());
+        }
+        if self.config.cv {
+            header.extend(per_metric(&|name| format!("{} cv", name)));
+        }
+        if self.config.median {
+            header.extend(per_metric(&|name| format!("median {}", name)));
+        }
+        if self.config.dew_point_spread {
+            header.push("avg dewpoint spread".to_string());
+        }
+        if self.apparent_temperature {
+            header.push("avg apparent temp".to_string());
+        }
+        if self.config.integrated_vpd {
+            header.push("vpd hours".to_string());
+        }
+        if self.config.ewma_alpha.is_some() {
+            header.push("ewma temp".to_string());
+        }
+        if !self.config.rails.is_empty() {
+            header.push("rail readings".to_string());
+        }
+        if self.config.baseline.is_some() {
+            header.extend(per_metric(&|name| format!("{} anomaly", name)));
+        }
+        if let Some(percentile) = self.config.percentile {
+            header.extend(per_metric(&|name| format!("p{} {}", format_number(percentile, None), name)));
+        }
+        if let Some(column) = &self.config.flag_column {
+            header.push(flag_header(column));
+        }
+        if !self.config.extra_gdd_bases.is_empty() {
+            header.extend(self.config.gdd_bases().iter().map(|base| format!("gdd {}", format_number(*base, None))));
+        }
+        for metric in custom_metrics {
+            header.extend(["avg", "max", "min"].iter().map(|aggregate| format!("{} {}", aggregate, metric.name())));
+        }
+        header.push("event".to_string());
+        header
+    }
+
+    fn row(&mut self, day_summary: &DaySummaryStats<NaiveDate>) -> Vec<Cell> {
+        let config = self.config;
+        let event = config.event_aggregation.apply(&day_summary.events);
+
+        // A reset event (e.g. at planting) starts the running gdd totals over from its day.
+        if day_summary.resets_gdd() {
+            self.total_gdd = 0.0;
+            self.total_gdd_by_base.iter_mut().for_each(|total_gdd| *total_gdd = 0.0);
+        }
+        // A day without any temperature readings has no gdd and adds nothing to the running total.
+        if !day_summary.gdd.is_nan() {
+            self.total_gdd += day_summary.gdd;
+        }
+
+        let mut row = vec![
+            Cell::Text(config.date_label(day_summary.date)),
+            self.mean_cell(day_summary, Metric::Temperature),
+            Cell::Number(day_summary.value(Metric::Temperature, Aggregate::Max)),
+            Cell::Number(day_summary.value(Metric::Temperature, Aggregate::Min)),
+            self.mean_cell(day_summary, Metric::Humidity),
+            Cell::Number(day_summary.value(Metric::Humidity, Aggregate::Max)),
+            Cell::Number(day_summary.value(Metric::Humidity, Aggregate::Min)),
+            self.mean_cell(day_summary, Metric::DewPoint),
+            self.mean_cell(day_summary, Metric::Vpd),
+            Cell::Number(self.total_gdd),
+        ];
+        if config.freeze_column {
+            row.push(Cell::Flag(day_summary.is_freeze_day(config.freeze_threshold())));
+        }
+        if let Some(base) = config.degree_day_base {
+            let (cap_low, cap_high) = config.degree_day_caps();
+            self.total_degree_days += day_summary.degree_day(base, cap_low, cap_high, config.degree_day_direction);
+            row.push(Cell::Number(self.total_degree_days));
+        }
+        if config.gdd_both_units {
+            row.push(Cell::Number(config.scale.degree_days_in(self.total_gdd, TemperatureScale::Fahrenheit)));
+            row.push(Cell::Number(config.scale.degree_days_in(self.total_gdd, TemperatureScale::Celsius)));
+        }
+        if config.show_raw_gdd {
+            // What the day adds to the cumulative gdd: the raw gdd floored at zero by the methods
+            // that floor it, and left as it is by the mean method.
+            row.push(Cell::Number(day_summary.raw_gdd_for_base(config.gdd_method, config.gdd_base(), config.degree_day_caps(), 0)));
+            row.push(Cell::Number(day_summary.gdd_for_base(config.gdd_method, config.gdd_base(), config.degree_day_caps(), 0)));
+        }
+        if config.gdd_settings_columns {
+            row.push(Cell::Text(config.gdd_method.to_string()));
+            row.push(Cell::Number(config.gdd_base()));
+        }
+        if let Some(gdd_target) = config.gdd_target {
+            let percent = (self.total_gdd as f64 / gdd_target as f64 * 100.0) as f32;
+            row.push(Cell::Number(if config.gdd_target_overshoot { percent } else { percent.min(100.0) }));
+        }
+        if let Some(interval) = config.expected_interval {
+            row.push(Cell::Number(day_summary.completeness(interval)));
+        }
+        if config.interpolate_days {
+            row.push(Cell::Flag(day_summary.interpolated));
+        }
+        if config.cv {
+            for metric in Metric::ALL {
+                row.push(day_summary.cv(metric).map_or(Cell::Missing, Cell::Number));
+            }
+        }
+        if config.median {
+            for metric in Metric::ALL {
+                row.push(Cell::Number(day_summary.value(metric, Aggregate::Median)));
+            }
+        }
+        if config.dew_point_spread {
+            row.push(Cell::Number(day_summary.dew_point_spread_stats.mean));
+        }
+        if self.apparent_temperature {
+            row.push(Cell::Number(day_summary.apparent_temperature_stats.mean));
+        }
+        if config.integrated_vpd {
+            row.push(Cell::Number(day_summary.vpd_hours));
+        }
+        if let Some(alpha) = config.ewma_alpha {
+            // Each day moves the average `alpha` of the way towards its mean; days without a mean
+            // temperature leave it as it was.
+            let mean = day_summary.value(Metric::Temperature, Aggregate::Mean);
+            if !mean.is_nan() {
+                self.ewma_temperature = Some(self.ewma_temperature.map_or(mean, |ewma| ewma + alpha * (mean - ewma)));
+            }
+            row.push(self.ewma_temperature.map_or(Cell::Missing, Cell::Number));
+        }
+        if !config.rails.is_empty() {
+            row.push(Cell::Number(day_summary.rail_readings as f32));
+        }
+        // A day of the year missing from the baseline has no anomalies.
+        if config.baseline.is_some() {
+            let normals = self.baseline.get(&day_of_leap_year(day_summary.date));
+            for (index, metric) in Metric::ALL.iter().enumerate() {
+                let normal = normals.map_or(f32::NAN, |normals| normals[index]);
+                row.push(Cell::Number(day_summary.value(*metric, Aggregate::Mean) - normal));
+            }
+        }
+        if let Some(percentile) = config.percentile {
+            for metric in Metric::ALL {
+                row.push(Cell::Number(day_summary.stats(metric).percentile(percentile as f64 / 100.0, config.percentile_method)));
+            }
+        }
+        if config.flag_column.is_some() {
+            row.push(Cell::Flag(day_summary.flag));
+        }
+        if !config.extra_gdd_bases.is_empty() {
+            for (base_index, (total_gdd, base)) in self.total_gdd_by_base.iter_mut().zip(config.gdd_bases()).enumerate() {
+                let gdd = day_summary.gdd_for_base(config.gdd_method, base, config.degree_day_caps(), base_index);
+                if !gdd.is_nan() {
+                    *total_gdd += gdd;
                 }
-            },
-            None => {
-                self.0.push(DaySummaryStats::from_record(record));
+                row.push(Cell::Number(*total_gdd));
             }
         }
+        for metric_stats in day_summary.custom_stats() {
+            row.extend([metric_stats.mean, metric_stats.max, metric_stats.min].iter().map(|value| Cell::Number(*value)));
+        }
+        row.push(Cell::Text(event));
+        row
+    }
+
+    // One standalone JSON object per row, keyed by the header names in snake case.
+    fn json_line(&mut self, day_summary: &DaySummaryStats<NaiveDate>) -> String {
+        let fields: Vec<String> = self.header().iter().zip(self.row(day_summary).iter())
+            .map(|(name, cell)| format!("\"{}\":{}", name.replace(' ', "_"), cell.to_json(self.config.round)))
+            .collect();
+        format!("{{{}}}", fields.join(","))
     }
 }
 
-#[derive(Debug, Clone)]
-struct TemperatureStats {
-    pub max_temperature: f32,
-    pub min_temperature: f32,
-    pub mean_temperature: f32,
-    pub median_temperature: f32,
-    pub temperature_entries: Vec<f32>,
-    pub temperature_sum: f32,
+// Writes the outputs that are produced while the data is read: the clean and rejected row
+// copies, and json, whose objects are flushed as soon as each day is complete for anyone tailing
+// the output.
+struct StreamingOutputs<'a> {
+    config: &'a Config,
+    max_date: NaiveDate,
+    clean_writer: Option<csv::Writer<File>>,
+    rejects_writer: Option<csv::Writer<File>>,
+    headers_written: bool, // each group is read separately, but the row copies get a single header.
+    json_output: Option<(BufWriter<File>, RowBuilder<'a>)>,
+    json_rows: usize, // the objects written so far, which a json array separates with commas.
+    by_hour: HashMap<Metric, HourOfDayProfile>, // the readings --diurnal and --vpd-by-hour need, pooled across days by their hour of day.
+    row_errors: Vec<RowError>,
+    warnings: Vec<String>,
+    tally: RowTally, // summed over the groups.
 }
 
-impl fmt::Display for TemperatureStats {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "temp: mean: {} max: {} min: {}",
-            self.mean_temperature,
-            self.max_temperature,
-            self.min_temperature,
-        )
+impl<'a> ProcessObserver for StreamingOutputs<'a> {
+    fn on_headers(&mut self, headers: &csv::StringRecord) -> Result<(), SensoryError> {
+        if self.headers_written {
+            return Ok(());
+        }
+        for writer in self.clean_writer.iter_mut().chain(self.rejects_writer.iter_mut()) {
+            writer.write_record(headers)?;
+        }
+        self.headers_written = true;
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-struct HumidityStats {
-    pub max_humidity: f32,
-    pub min_humidity: f32,
-    pub mean_humidity: f32,
-    pub median_humidity: f32,
-    pub humidity_entries: Vec<f32>,
-    pub humidity_sum: f32,
-}
+    fn on_record(&mut self, raw: &csv::StringRecord, record: &SensorRecord<NaiveDate>) -> Result<(), SensoryError> {
+        if let Some(writer) = self.clean_writer.as_mut() {
+            writer.write_record(raw)?;
+        }
+        // Only --diurnal and --vpd-by-hour report the hour-of-day profiles, so only they pay for them.
+        let profiled: &[Metric] = match (self.config.diurnal.is_some(), self.config.vpd_by_hour) {
+            (true, _) => &Metric::ALL,
+            (false, true) => &[Metric::Vpd],
+            (false, false) => &[],
+        };
+        if !(self.config.drop_future && record.is_future(self.max_date)) {
+            for &metric in profiled {
+                self.by_hour.entry(metric).or_default().add(record.hour(), metric.reading(record));
+            }
+        }
+        Ok(())
+    }
 
-impl fmt::Display for HumidityStats {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "humidity: mean: {} max: {} min: {}",
-            self.mean_humidity,
-            self.max_humidity,
-            self.min_humidity,
-        )
+    fn on_reject(&mut self, raw: &csv::StringRecord, err: &RowError) -> Result<(), SensoryError> {
+        if let Some(writer) = self.rejects_writer.as_mut() {
+            writer.write_record(raw)?;
+        }
+        self.row_errors.push(err.clone());
+        Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-struct DewPointStats {
-    pub max_dew_point: f32,
-    pub min_dew_point: f32,
-    pub mean_dew_point: f32,
-    pub median_dew_point: f32,
-    pub dew_point_entries: Vec<f32>,
-    pub dew_point_sum: f32,
-}
+    fn on_day_complete(&mut self, day_summary: &DaySummaryStats<NaiveDate>) -> Result<(), SensoryError> {
+        if let Some((writer, row_builder)) = self.json_output.as_mut() {
+            let object = row_builder.json_line(day_summary);
+            match self.config.format {
+                OutputFormat::Json => write!(writer, "{}\n{}", if self.json_rows == 0 { "[" } else { "," }, object)?,
+                _ => writeln!(writer, "{}", object)?,
+            }
+            writer.flush()?;
+            self.json_rows += 1;
+        }
+        Ok(())
+    }
 
-impl fmt::Display for DewPointStats {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "dew_point: mean: {} max: {} min: {}",
-            self.mean_dew_point,
-            self.max_dew_point,
-            self.min_dew_point,
-        )
+    fn on_warning(&mut self, warning: &str) {
+        self.warnings.push(warning.to_string());
     }
-}
 
-#[derive(Debug, Clone)]
-struct VPDStats {
-    pub max_vpd: f32,
-    pub min_vpd: f32,
-    pub mean_vpd: f32,
-    pub median_vpd: f32,
-    pub vpd_entries: Vec<f32>,
-    pub vpd_sum: f32,
-}
+    fn on_tally(&mut self, tally: &RowTally) {
+        self.tally.add(tally);
+    }
 
-impl fmt::Display for VPDStats {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "vpd: mean: {} max: {} min: {}",
-            self.mean_vpd,
-            self.max_vpd,
-            self.min_vpd,
-        )
+    fn interrupted(&self) -> bool {
+        INTERRUPTED.load(Ordering::SeqCst)
     }
 }
 
-#[derive(Debug, Clone)]
-struct DaySummaryStats<T> {
-    pub date: T,
-    pub temperature_stats: TemperatureStats,
-    pub humidity_stats: HumidityStats,
-    pub dew_point_stats: DewPointStats,
-    pub vpd_stats: VPDStats,
-    pub gdd: f32, // gdd is growing degree days, a measure of heat units per day a crop receives over its lifetime relative to the minimum base temperature required for growth of that crop. e.g. corn's base temperature is 50°F. Given a day whose average temperature was 75°F, the crop would have grown by 1.5 gdd (75°F - 50°F = 15 gdd).
+// Lets the first Ctrl-C end the read gracefully. The default handling is restored right away, so
+// a second Ctrl-C still stops the run immediately.
+#[cfg(unix)]
+fn handle_interrupts() {
+    extern "C" fn on_interrupt(_signal: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        unsafe {
+            libc::signal(libc::SIGINT, libc::SIG_DFL);
+        }
+    }
+    unsafe {
+        libc::signal(libc::SIGINT, on_interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
 }
 
-// TODO: This should be configurable as either an env var or a cli arg.
-static GDD_THRESHOLD : f32 = 65.0;
-impl DaySummaryStats<NaiveDate> {
-    fn from_record(record: &SensorRecord<NaiveDate>) -> Self {
-        let temperature_stats = TemperatureStats {
-            max_temperature: record.temperature,
-            min_temperature: record.temperature,
-            mean_temperature: record.temperature,
-            median_temperature: record.temperature,
-            temperature_entries: vec![record.temperature],
-            temperature_sum: record.temperature,
-        };
-        let humidity_stats = HumidityStats {
-            max_humidity: record.humidity,
-            min_humidity: record.humidity,
-            mean_humidity: record.humidity,
-            median_humidity: record.humidity,
-            humidity_entries: vec![record.humidity],
-            humidity_sum: record.humidity,
-        };
-        let dew_point_stats = DewPointStats {
-            max_dew_point: record.dew_point,
-            min_dew_point: record.dew_point,
-            mean_dew_point: record.dew_point,
-            median_dew_point: record.dew_point,
-            dew_point_entries: vec![record.dew_point],
-            dew_point_sum: record.dew_point,
-        };
-        let vpd_stats = VPDStats {
-            max_vpd: record.vpd,
-            min_vpd: record.vpd,
-            mean_vpd: record.vpd,
-            median_vpd: record.vpd,
-            vpd_entries: vec![record.vpd],
-            vpd_sum: record.vpd,
-        };
-        return DaySummaryStats {
-            date: record.timestamp,
-            temperature_stats: temperature_stats,
-            humidity_stats: humidity_stats,
-            dew_point_stats: dew_point_stats,
-            vpd_stats: vpd_stats,
-            gdd: record.temperature - GDD_THRESHOLD,
-        };
-    }
+#[cfg(not(unix))]
+fn handle_interrupts() {}
 
-    fn calc_temperature_stats(&mut self, record: &SensorRecord<NaiveDate>) {
-        // Add the temperature to the accumulated sum
-        self.temperature_stats.temperature_sum += record.temperature;
+// Where an interrupted run records the last day it completed, next to the output.
+fn checkpoint_path(config: &Config) -> String {
+    format!("{}.checkpoint", config.output_path())
+}
 
-        // First add the record to the temperature stat entries.
-        self.temperature_stats.temperature_entries.push(record.temperature);
+// What a resumed run needs to carry on from an interrupted one: the last day written, and the
+// running totals of the cumulative columns through it. Written as the date on the first line
+// followed by a `name=value` line per total, e.g. `total_gdd=152.5`; a missing ewma is empty.
+#[derive(Clone)]
+struct Checkpoint {
+    last_day: NaiveDate,
+    total_gdd: f32,
+    total_gdd_by_base: Vec<f32>,
+    total_degree_days: f32,
+    ewma_temperature: Option<f32>,
+}
 
-        // Find the max temperature.
-        self.temperature_stats.max_temperature = *self.temperature_stats.temperature_entries.iter().max_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+impl Checkpoint {
+    fn new(last_day: NaiveDate, row_builder: &RowBuilder) -> Self {
+        Checkpoint {
+            last_day,
+            total_gdd: row_builder.total_gdd,
+            total_gdd_by_base: row_builder.total_gdd_by_base.clone(),
+            total_degree_days: row_builder.total_degree_days,
+            ewma_temperature: row_builder.ewma_temperature,
+        }
+    }
 
-        // Find the min temperature.
-        self.temperature_stats.min_temperature = *self.temperature_stats.temperature_entries.iter().min_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let last_day = lines.next()?.trim().parse().ok()?;
+        let totals: HashMap<&str, &str> = lines.filter_map(|line| line.split_once('=')).collect();
+        let total = |name: &str| totals.get(name)?.trim().parse::<f32>().ok();
+        Some(Checkpoint {
+            last_day,
+            total_gdd: total("total_gdd")?,
+            total_gdd_by_base: totals.get("total_gdd_by_base")?.split(',').filter(|total| !total.is_empty()).map(|total| total.trim().parse().ok()).collect::<Option<Vec<f32>>>()?,
+            total_degree_days: total("total_degree_days")?,
+            ewma_temperature: match totals.get("ewma_temperature")?.trim() {
+                "" => None,
+                ewma => Some(ewma.parse().ok()?),
+            },
+        })
+    }
 
-        // Find the median temperature.
-        let median_index = self.temperature_stats.temperature_entries.len() / 2;
-        self.temperature_stats.median_temperature = self.temperature_stats.temperature_entries[median_index];
+    // Picks up the running totals where the interrupted run left them.
+    fn restore(&self, row_builder: &mut RowBuilder) {
+        row_builder.total_gdd = self.total_gdd;
+        row_builder.total_gdd_by_base = self.total_gdd_by_base.clone();
+        row_builder.total_degree_days = self.total_degree_days;
+        row_builder.ewma_temperature = self.ewma_temperature;
+    }
+}
 
-        // Find the mean temperature.
-        let mean_denominator = self.temperature_stats.temperature_entries.len() as f32;
-        self.temperature_stats.mean_temperature = self.temperature_stats.temperature_sum / mean_denominator;
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.last_day)?;
+        writeln!(f, "total_gdd={}", self.total_gdd)?;
+        writeln!(f, "total_gdd_by_base={}", self.total_gdd_by_base.iter().map(f32::to_string).collect::<Vec<String>>().join(","))?;
+        writeln!(f, "total_degree_days={}", self.total_degree_days)?;
+        writeln!(f, "ewma_temperature={}", self.ewma_temperature.map_or(String::new(), |ewma| ewma.to_string()))
     }
+}
 
-    fn calc_humidity_stats(&mut self, record: &SensorRecord<NaiveDate>) {
-        // Add the humidity to the accumulated sum
-        self.humidity_stats.humidity_sum += record.humidity;
+// Where a watched run leaves off for the next one: the checkpoint through the day before the
+// last, and the length of the output up to the end of that day's row. The last day may still be
+// receiving readings, so the next run drops its row and summarizes it again.
+#[derive(Clone)]
+struct WatchPoint {
+    checkpoint: Checkpoint,
+    output_length: u64,
+}
+
+// Whether a watched re-run can pick up after the days already written and append the rest. That
+// takes a plain csv of a row per day, with nothing that depends on the whole run: no reports, no
+// other outputs and no z-scores. Anything else is summarized from the start again.
+fn watch_resumes(config: &Config) -> bool {
+    config.format == OutputFormat::Csv && !config.pivot && !config.metrics_only && config.collapse_stable.is_none() && config.group_by.is_none()
+        && config.annotations.is_none() && !config.normalize && !config.interpolate_days && config.explain.is_none()
+        && config.clean_output.is_none() && config.rejects_output.is_none() && config.report_json.is_none() && config.diurnal.is_none() && !config.vpd_by_hour
+        && !config.frost_dates && !config.season_report && config.climatology.is_none() && config.fit_gdd_base.is_none() && !config.clock_diagnostics
+}
 
-        // First add the record to the humidity stat entries.
-        self.humidity_stats.humidity_entries.push(record.humidity);
+// A failed run exits with its error's message; returning the error from `main` would print its
+// `Debug` form instead.
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
 
-        // Find the max humidity.
-        self.humidity_stats.max_humidity = *self.humidity_stats.humidity_entries.iter().max_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+fn run() -> Result<(), SensoryError> {
+    let mut args = env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("generate") {
+        args.next();
+        let generator = Generator::from_args(args).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            process::exit(1);
+        });
+        match &generator.output {
+            Some(path) => generator.generate(File::create(path)?)?,
+            None => generator.generate(io::stdout().lock())?,
+        }
+        return Ok(());
+    }
 
-        // Find the min humidity.
-        self.humidity_stats.min_humidity = *self.humidity_stats.humidity_entries.iter().min_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    let config = Config::from_args(args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    });
+
+    if let Some(other_path) = &config.compare {
+        let diffs = compare_summaries(File::open(config.output_path())?, File::open(other_path)?)?;
+        let format_value = |value: Option<f32>| value.map_or(String::new(), |value| format_number(value, config.round));
+        let mut writer = csv::Writer::from_writer(io::stdout().lock());
+        writer.write_record(["date", "metric", "value", "other value", "difference", "percent difference"])?;
+        for diff in &diffs {
+            writer.write_record(&[
+                diff.date.clone(),
+                diff.metric.clone(),
+                format_value(diff.value),
+                format_value(diff.other),
+                format_value(diff.difference()),
+                format_value(diff.percent_difference()),
+            ])?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
 
-        // Find the median humidity.
-        let median_index = self.humidity_stats.humidity_entries.len() / 2;
-        self.humidity_stats.median_humidity = self.humidity_stats.humidity_entries[median_index];
+    if !config.watch {
+        handle_interrupts();
+        let result = summarize(config, None);
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        return result.map(|_| ());
+    }
+    // Watching re-runs the summary on each change, resuming after the last complete day where it
+    // can (see `watch_resumes`). A failed run is reported and the watch carries on, so that a
+    // half-written file doesn't end it.
+    let mut modified = input_modified(&config);
+    let mut watch_point = None;
+    loop {
+        match summarize(config.clone(), watch_point.as_ref()) {
+            Ok(next_point) => watch_point = next_point,
+            Err(err) => eprintln!("{}", err),
+        }
+        eprintln!("watching {} for changes", config.input_path());
+        let previous = modified;
+        modified = wait_for_change(&config, modified);
+        // An input that got shorter was rewritten rather than added to, so it starts over.
+        if modified.map(|(_, length)| length) < previous.map(|(_, length)| length) {
+            watch_point = None;
+        }
+    }
+}
 
-        // Find the mean humidity.
-        let mean_denominator = self.humidity_stats.humidity_entries.len() as f32;
-        self.humidity_stats.mean_humidity = self.humidity_stats.humidity_sum / mean_denominator;
+// The input's modification time and length, which together tell whether it has changed. For a
+// directory of daily files, the latest time and total length of the files.
+fn input_modified(config: &Config) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(config.input_path()).ok()?;
+    if !metadata.is_dir() {
+        return Some((metadata.modified().ok()?, metadata.len()));
+    }
+    let mut modified = (metadata.modified().ok()?, 0);
+    for file in daily_files(&config.input_path()).ok()? {
+        let metadata = fs::metadata(file).ok()?;
+        modified = (modified.0.max(metadata.modified().ok()?), modified.1 + metadata.len());
     }
+    Some(modified)
+}
 
-    fn calc_dew_point_stats(&mut self, record: &SensorRecord<NaiveDate>) {
-        // Add the humidity to the accumulated sum
-        self.dew_point_stats.dew_point_sum += record.dew_point;
+// Polls the input until it differs from `last` and then stays the same for a whole poll, so that
+// a burst of writes triggers a single re-run. A missing input is waited out. Returns the settled
+// state.
+fn wait_for_change(config: &Config, last: Option<(SystemTime, u64)>) -> Option<(SystemTime, u64)> {
+    let mut previous = last;
+    loop {
+        thread::sleep(WATCH_POLL_INTERVAL);
+        let current = input_modified(config);
+        if current.is_some() && current != last && current == previous {
+            return current;
+        }
+        previous = current;
+    }
+}
 
-        // First add the record to the humidity stat entries.
-        self.dew_point_stats.dew_point_entries.push(record.dew_point);
+// Reads, summarizes, and writes the outputs and reports for one run. A watched run resumes from
+// `watch_point`, left by the previous run, and returns the one for the next.
+fn summarize(mut config: Config, watch_point: Option<&WatchPoint>) -> Result<Option<WatchPoint>, SensoryError> {
+    let resumed_from = if config.resume {
+        let checkpoint = fs::read_to_string(checkpoint_path(&config)).ok().and_then(|checkpoint| Checkpoint::parse(&checkpoint));
+        Some(checkpoint.ok_or_else(|| SensoryError::Config(format!("No checkpoint to resume from at {}", checkpoint_path(&config))))?)
+    } else if let Some(watch_point) = watch_point {
+        eprintln!("resuming after {}", watch_point.checkpoint.last_day);
+        Some(watch_point.checkpoint.clone())
+    } else {
+        None
+    };
+    config.resume_after = resumed_from.as_ref().map(|checkpoint| checkpoint.last_day);
+    // Each pass over the input opens it afresh, so none of it is held in memory.
+    if config.units_row {
+        if let Some(units) = read_units_row(open_input(&config)?)? {
+            config.apply_units_row(&units)?;
+        }
+    }
+    if let Some(path) = &config.calibration {
+        let calibrations = read_calibration(File::open(path)?)?;
+        for (metric, calibration) in calibrations {
+            eprintln!("calibrating {}: {}", metric, calibration);
+            config.calibrations.insert(metric, calibration);
+        }
+    }
+    let baseline: HashMap<u32, Vec<f32>> = match &config.baseline {
+        Some(path) => read_baseline(File::open(path)?)?.into_iter().map(|normals| (normals.day_of_year, normals.means)).collect(),
+        None => HashMap::new(),
+    };
+    let has_wind_column = csv::Reader::from_reader(open_input(&config)?).headers().ok().and_then(wind_speed_column).is_some();
+
+    let output_path = config.output_path();
+    let mut outputs = StreamingOutputs {
+        config: &config,
+        max_date: config.max_date(),
+        clean_writer: match &config.clean_output {
+            Some(path) => Some(csv::Writer::from_path(path)?),
+            None => None,
+        },
+        rejects_writer: match &config.rejects_output {
+            Some(path) => Some(csv::WriterBuilder::new().flexible(true).from_path(path)?),
+            None => None,
+        },
+        json_output: match config.format {
+            OutputFormat::Json | OutputFormat::JsonLines => Some((BufWriter::new(File::create(&output_path)?), RowBuilder { baseline: baseline.clone(), ..RowBuilder::new(&config, has_wind_column) })),
+            OutputFormat::Csv => None,
+        },
+        json_rows: 0,
+        headers_written: false,
+        by_hour: HashMap::new(),
+        row_errors: Vec::new(),
+        warnings: Vec::new(),
+        tally: RowTally::default(),
+    };
 
-        // Find the max humidity.
-        self.dew_point_stats.max_dew_point = *self.dew_point_stats.dew_point_entries.iter().max_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    // Without --group-by (or without any rows to label) there's a single, unlabelled group.
+    let mut labels: Vec<Option<String>> = match config.group_by {
+        Some(_) => group_labels(open_input(&config)?, &config)?.into_iter().map(Some).collect(),
+        None => Vec::new(),
+    };
+    if labels.is_empty() {
+        labels.push(None);
+    }
+    let mut groups = Vec::new();
+    for label in labels {
+        let events = match &config.events {
+            Some(path) => Some(File::open(path)?),
+            None => None,
+        };
+        let mut day_summaries = process_group(&config, open_input(&config)?, events, &mut outputs, label.as_deref())?;
+        if config.exact_medians {
+            apply_exact_medians(&config, open_input(&config)?, &mut day_summaries, label.as_deref())?;
+        }
+        groups.push((label, day_summaries));
+    }
 
-        // Find the min humidity.
-        self.dew_point_stats.min_dew_point = *self.dew_point_stats.dew_point_entries.iter().min_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    for writer in outputs.clean_writer.iter_mut().chain(outputs.rejects_writer.iter_mut()) {
+        writer.flush()?;
+    }
+    if let (OutputFormat::Json, Some((writer, _))) = (config.format, outputs.json_output.as_mut()) {
+        writeln!(writer, "{}", if outputs.json_rows == 0 { "[]" } else { "\n]" })?;
+        writer.flush()?;
+    }
+    if !outputs.row_errors.is_empty() {
+        eprintln!("{} rows rejected:", outputs.row_errors.len());
+        for err in &outputs.row_errors {
+            eprintln!("  {}", err);
+        }
+    }
+    for warning in &outputs.warnings {
+        eprintln!("warning: {}", warning);
+    }
+    let mut warning_count = outputs.row_errors.len() + outputs.warnings.len();
+    for (label, day_summaries) in &groups {
+        let gdd_decreases = day_summaries.gdd_decreases();
+        if let Some(first_decrease) = gdd_decreases.first() {
+            let group = label.as_ref().map_or(String::new(), |label| format!(" in group {:?}", label));
+            eprintln!("warning: cumulative gdd decreases below zero on {} days{}, first on {}; check that the gdd base ({}) suits the data", gdd_decreases.len(), group, first_decrease, config.gdd_base());
+            warning_count += 1;
+        }
+    }
+    eprintln!("{}.", outputs.tally);
+    // Rejected rows count as warnings too, since they are data quality problems all the same.
+    if config.fail_on_warning && warning_count > 0 {
+        return Err(SensoryError::FailOnWarning(warning_count));
+    }
+    // The reports are limited to ungrouped runs, which have just the one group.
+    let day_summaries = &groups[0].1;
+
+    if config.clock_diagnostics {
+        let issues = clock_issues(open_input(&config)?, &config)?;
+        let backwards = issues.iter().filter(|issue| matches!(issue, ClockIssue::Backwards { .. })).count();
+        println!("clock diagnostics: {} backwards jumps, {} stalled timestamps", backwards, issues.len() - backwards);
+        for issue in &issues {
+            println!("  {}", issue);
+        }
+    }
 
-        // Find the median humidity.
-        let median_index = self.dew_point_stats.dew_point_entries.len() / 2;
-        self.dew_point_stats.median_dew_point = self.dew_point_stats.dew_point_entries[median_index];
+    let hour_of_day = |metric: Metric| outputs.by_hour.get(&metric).cloned().unwrap_or_default();
+    if config.vpd_by_hour {
+        let vpd_by_hour = hour_of_day(Metric::Vpd);
+        println!("mean vpd by hour of day:");
+        for (hour, mean) in vpd_by_hour.means().iter().enumerate() {
+            if let Some(mean) = mean {
+                println!("  {:02}:00 {}", hour, mean);
+            }
+        }
+        if let (Some((driest_hour, driest_vpd)), Some((wettest_hour, wettest_vpd))) = (vpd_by_hour.max_hour(), vpd_by_hour.min_hour()) {
+            println!("driest hour: {:02}:00 (mean vpd {})", driest_hour, driest_vpd);
+            println!("wettest hour: {:02}:00 (mean vpd {})", wettest_hour, wettest_vpd);
+        }
+    }
 
-        // Find the mean humidity.
-        let mean_denominator = self.dew_point_stats.dew_point_entries.len() as f32;
-        self.dew_point_stats.mean_dew_point = self.dew_point_stats.dew_point_sum / mean_denominator;
+    // The typical day has a row for every hour, left blank for a metric without readings in it.
+    if let Some(path) = &config.diurnal {
+        let means: Vec<Vec<Option<f32>>> = Metric::ALL.iter().map(|&metric| hour_of_day(metric).means()).collect();
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(["hour", "avg temp", "avg humidity", "avg dewpoint", "avg vpd"])?;
+        for hour in 0..24 {
+            let mut record = vec![hour.to_string()];
+            record.extend(means.iter().map(|means| means[hour].map_or(String::new(), |mean| format_number(mean, config.round))));
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
     }
 
-    fn calc_vpd_stats(&mut self, record: &SensorRecord<NaiveDate>) {
-        // Add the humidity to the accumulated sum
-        self.vpd_stats.vpd_sum += record.vpd;
+    let report = Report { tally: Some(outputs.tally), ..analyze_summaries(day_summaries, &config) };
+    if let Some(path) = &config.report_json {
+        fs::write(path, report.to_json() + "\n")?;
+    }
 
-        // First add the record to the humidity stat entries.
-        self.vpd_stats.vpd_entries.push(record.vpd);
+    if let Some(frost_seasons) = &report.frost_seasons {
+        let format_date = |date: Option<NaiveDate>| date.map_or("none".to_string(), |date| date.to_string());
+        for frost_season in frost_seasons {
+            match frost_season.frost_free_days() {
+                Some(frost_free_days) => println!("{}: last spring frost {}, first fall frost {}, {} frost-free days",
+                    frost_season.year, format_date(frost_season.last_spring_frost), format_date(frost_season.first_fall_frost), frost_free_days),
+                None if frost_season.last_spring_frost.is_none() && frost_season.first_fall_frost.is_none() => println!("{}: no frost recorded", frost_season.year),
+                None => println!("{}: last spring frost {}, first fall frost {}",
+                    frost_season.year, format_date(frost_season.last_spring_frost), format_date(frost_season.first_fall_frost)),
+            }
+        }
+    }
 
-        // Find the max humidity.
-        self.vpd_stats.max_vpd = *self.vpd_stats.vpd_entries.iter().max_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    if let Some(season_stats) = &report.season_stats {
+        let metrics = config.summary_metrics();
+        println!("season summary:");
+        for season_stats in season_stats {
+            println!("  {} ({}): mean {}, max {} on {}, min {} on {}", season_stats.metric, metrics[season_stats.metric.index()].unit(),
+                season_stats.mean, season_stats.max, season_stats.max_date, season_stats.min, season_stats.min_date);
+        }
+    }
 
-        // Find the min humidity.
-        self.vpd_stats.min_vpd = *self.vpd_stats.vpd_entries.iter().min_by(|x, y| x.partial_cmp(&y).unwrap()).unwrap();
+    if let (Some(path), Some(climatology)) = (&config.climatology, &report.climatology) {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(["day of year", "avg temp", "avg humidity", "avg dewpoint", "avg vpd", "years"])?;
+        for climatology_day in climatology {
+            let mut record = vec![climatology_day.day_of_year.to_string()];
+            record.extend(climatology_day.means.iter().map(|mean| if mean.is_nan() { String::new() } else { format_number(*mean, config.round) }));
+            record.push(climatology_day.years.to_string());
+            writer.write_record(&record)?;
+        }
+        writer.flush()?;
+    }
 
-        // Find the median humidity.
-        let median_index = self.vpd_stats.vpd_entries.len() / 2;
-        self.vpd_stats.median_vpd = self.vpd_stats.vpd_entries[median_index];
+    if config.fit_gdd_base.is_some() {
+        match report.gdd_base_fit {
+            Some(fit) => println!("best-fit gdd base: {} (cv {})", fit.base, fit.cv),
+            None => eprintln!("Unable to fit a gdd base: at least three events with accumulated heat are required."),
+        }
+    }
 
-        // Find the mean humidity.
-        let mean_denominator = self.vpd_stats.vpd_entries.len() as f32;
-        self.vpd_stats.mean_vpd = self.vpd_stats.vpd_sum / mean_denominator;
+    if let Some(explain_date) = config.explain {
+        match day_summaries.iter().find(|day_summary| day_summary.date == explain_date) {
+            Some(day_summary) => print!("{}", day_summary.explain_gdd(&config)),
+            None => eprintln!("No readings found for {}", explain_date),
+        }
+        return Ok(None);
     }
 
-    fn calc_growing_degrees_day(&mut self) {
-        // TODO: calculate GDD for day and night. This calculation currently uses 1 value for a 24 hour time period.
-        self.gdd = self.temperature_stats.mean_temperature - GDD_THRESHOLD;
-        // If degree day is long or short, the calculation is slightly different:
-        // if degree_day.short() {
-        //    gdd.growing_degrees_day = (day_summary.temperature_stats.mean_day_temperature + day_summary.temperature_stats.mean_night_temperature) / 2.0;
-        // } else {
-        //    gdd.growing_degrees_day = ((day_summary.temperature_stats.mean_day_temperature * 0.67) + (day_summary.temperature_stats.mean_night_temperature * 0.33)) / 2.0;
-        // }
+    if outputs.json_output.is_some() {
+        println!("day summaries: {}", day_summaries);
+        return Ok(None);
     }
-}
 
-fn main() -> Result<(), csv::Error> {
-    let sensor_data = fs::read_to_string("data/example.csv").expect("Error reading csv file.");
-    let mut sensor_reader = csv::Reader::from_reader(sensor_data.as_bytes());
-
-    let event_data = fs::read_to_string("data/events.csv").expect("Error reading csv file.");
-    let mut event_reader = csv::Reader::from_reader(event_data.as_bytes());
-
-    let mut writer = csv::Writer::from_path("data/out_example.csv")?;
-    writer.write_record(&["date", "avg temp", "max temp", "min temp", "avg humidity", "max humidity", "min humidity", "avg dewpoint", "avg vpd", "gdd", "event"])?;
-
-    let mut event_summaries = HashMap::new();
-    for record in event_reader.records() {
-        let record: csv::StringRecord = record?;
-        let date_parts: Vec<&str> = record[0].split(" ").collect();
-        let date_vec: Vec<&str> = date_parts[0].split("-").collect();
-        let date = NaiveDate::from_ymd(
-                date_vec[0].parse::<i32>().unwrap(),
-                date_vec[1].parse::<u32>().unwrap(),
-                date_vec[2].parse::<u32>().unwrap(),
-            );
-        let event = record[1].parse::<String>().unwrap();
-        event_summaries.insert(date.to_string(), event);
-    }
-
-    let mut day_summaries = DaySummaries(Vec::new());
-    for record in sensor_reader.records() {
-        let record: csv::StringRecord = record?;
-        let record_entry = SensorRecord::from_csv_record(record);
-        day_summaries.add_record(&record_entry);
+    // In append mode the header is only written when starting a new (or empty) output file.
+    let write_header = !config.no_output_header && watch_point.is_none() && (!config.append || fs::metadata(&output_path).map_or(true, |metadata| metadata.len() == 0));
+    let output_file = match watch_point {
+        Some(watch_point) => {
+            let output_file = OpenOptions::new().append(true).open(&output_path)?;
+            output_file.set_len(watch_point.output_length)?;
+            output_file
+        },
+        None if config.append => OpenOptions::new().create(true).append(true).open(&output_path)?,
+        None => File::create(&output_path)?,
     };
+    let mut writer = csv::WriterBuilder::new().delimiter(config.out_delimiter()).quote_style(config.quote_style.to_csv()).from_writer(&output_file);
+    let mut header = RowBuilder::new(&config, has_wind_column).header();
+    if config.group_by.is_some() {
+        header.insert(0, "group".to_string());
+    }
+    let mut row_dates = Vec::new();
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut checkpoint = None;
+    // Where the next watched run picks up: see `WatchPoint`.
+    let mut settled_checkpoint = None;
+    let mut settled_length = 0;
+    for (label, day_summaries) in &groups {
+        match label {
+            Some(label) => println!("day summaries for {:?}: {}", label, day_summaries),
+            None => println!("day summaries: {}", day_summaries),
+        }
+        // Cumulative columns restart with each group, and z-scores are relative to the group.
+        let mut row_builder = RowBuilder::new(&config, has_wind_column);
+        row_builder.baseline = baseline.clone();
+        if let Some(resumed_from) = &resumed_from {
+            resumed_from.restore(&mut row_builder);
+        }
+        if config.normalize {
+            row_builder.normalization = Metric::ALL.iter().map(|&metric| day_summaries.daily_mean_distribution(metric)).collect();
+        }
+        // Each run of stable days becomes a single row dated by the run's date range (see
+        // `collapse_rows`).
+        let runs = match config.collapse_stable {
+            Some(tolerance) => day_summaries.stable_runs(tolerance),
+            None => (0..day_summaries.0.len()).map(|index| index..index + 1).collect(),
+        };
+        let row_header = row_builder.header();
+        for run in runs {
+            if run.start > 0 && run.end == day_summaries.0.len() {
+                settled_checkpoint = Some(Checkpoint::new(day_summaries.0[run.start - 1].date, &row_builder));
+            }
+            let days = &day_summaries.0[run];
+            let day_rows: Vec<Vec<Cell>> = days.iter().map(|day_summary| row_builder.row(day_summary)).collect();
+            let mut row: Vec<String> = collapse_rows(&row_header, &day_rows).iter().map(|cell| cell.to_csv(config.round)).collect();
+            if let [first, .., last] = days {
+                row[0] = format!("{}/{}", config.date_label(first.date), config.date_label(last.date));
+            }
+            if let Some(label) = label {
+                row.insert(0, label.clone());
+            }
+            row_dates.push(days[0].date);
+            rows.push(row);
+        }
+        checkpoint = day_summaries.0.last().map(|last_day| Checkpoint::new(last_day.date, &row_builder));
+    }
 
+    // A numeric matrix keeps just the number columns; the index file ties each (zero-based) row
+    // back to its group and date.
+    if config.metrics_only {
+        let label_columns = config.group_by.is_some() as usize;
+        if let Some(path) = &config.index_output {
+            let mut index_writer = csv::Writer::from_path(path)?;
+            index_writer.write_record(std::iter::once("row").chain(header[..=label_columns].iter().map(String::as_str)))?;
+            for (index, row) in rows.iter().enumerate() {
+                index_writer.write_record(std::iter::once(index.to_string()).chain(row[..=label_columns].iter().cloned()))?;
+            }
+            index_writer.flush()?;
+        }
+        let flag_column = config.flag_column.as_deref().map(flag_header);
+        let numeric_columns: Vec<bool> = header.iter()
+            .map(|name| !["group", "date", "freeze", "interpolated", "gdd method", "event"].contains(&name.as_str()) && Some(name) != flag_column.as_ref())
+            .collect();
+        let keep_numeric = |row: &Vec<String>| row.iter().zip(&numeric_columns).filter(|(_, numeric)| **numeric).map(|(value, _)| value.clone()).collect();
+        rows = rows.iter().map(keep_numeric).collect();
+        header = keep_numeric(&header);
+    }
 
-    println!("day summaries: {}", day_summaries);
-    let mut total_gdd = 0.0;
-    for day_summary in &day_summaries.0 {
-        let mut event = String::new();
-        if event_summaries.contains_key(&day_summary.date.to_string()) {
-            event = event_summaries.get(&day_summary.date.to_string()).unwrap().to_string();
-        }
-
-        total_gdd += day_summary.gdd;
-
-        writer.write_record(&[
-            day_summary.date.to_string(),
-            day_summary.temperature_stats.mean_temperature.to_string(),
-            day_summary.temperature_stats.max_temperature.to_string(),
-            day_summary.temperature_stats.min_temperature.to_string(),
-            day_summary.humidity_stats.mean_humidity.to_string(),
-            day_summary.humidity_stats.max_humidity.to_string(),
-            day_summary.humidity_stats.min_humidity.to_string(),
-            day_summary.dew_point_stats.mean_dew_point.to_string(),
-            day_summary.vpd_stats.mean_vpd.to_string(),
-            total_gdd.to_string(),
-            event.to_string(),
-        ])?;
-    };
+    if config.pivot {
+        // Transpose the table so each column becomes a row: one row per metric, one column per date.
+        // Long date ranges simply produce wide rows, which the csv writer handles without limits.
+        for (column, name) in header.iter().enumerate() {
+            let mut pivoted_row = vec![name.to_string()];
+            pivoted_row.extend(rows.iter().map(|row| row[column].clone()));
+            writer.write_record(&pivoted_row)?;
+        }
+    } else {
+        if write_header {
+            writer.write_record(&header)?;
+        }
+        // Annotations are written as `#` comment lines directly above the row whose day (or
+        // bucket) they fall in, so that csv readers with a comment prefix skip them.
+        let mut annotations = match &config.annotations {
+            Some(path) => read_events_with_format(File::open(path)?, config.event_date_format())?,
+            None => Vec::new(),
+        }.into_iter().peekable();
+        for (index, row) in rows.iter().enumerate() {
+            if index + 1 == rows.len() {
+                writer.flush()?;
+                settled_length = output_file.metadata()?.len();
+            }
+            let next_date = row_dates.get(index + 1).copied();
+            while let Some((date, note)) = annotations.next_if(|(date, _)| next_date.is_none_or(|next_date| *date < next_date)) {
+                writer.flush()?;
+                writeln!(&output_file, "# {}: {}", date, note.replace(['\r', '\n'], " "))?;
+            }
+            writer.write_record(row)?;
+        }
+    }
 
     writer.flush()?;
 
-    Ok(())
+    // The checkpoint goes with the rows it covers: an interrupted run writes it once those rows
+    // are flushed, and a resumed run that finishes removes it.
+    if INTERRUPTED.load(Ordering::SeqCst) && config.group_by.is_none() {
+        match checkpoint {
+            Some(checkpoint) => {
+                fs::write(checkpoint_path(&config), checkpoint.to_string())?;
+                eprintln!("Interrupted: wrote the days through {} to {}; re-run with --resume to continue.", checkpoint.last_day, output_path);
+            },
+            None => eprintln!("Interrupted before any day was completed."),
+        }
+    } else if config.resume {
+        fs::remove_file(checkpoint_path(&config))?;
+    }
+
+    if !config.watch || !watch_resumes(&config) {
+        return Ok(None);
+    }
+    // With just the one day since, the run picks up where the previous one did.
+    Ok(match settled_checkpoint {
+        Some(checkpoint) => Some(WatchPoint { checkpoint, output_length: settled_length }),
+        None => watch_point.cloned(),
+    })
 }
@@ -0,0 +1,612 @@
+// Reads the first worksheet of an Excel .xlsx workbook as csv, so that it goes through the same
+// parsing as a csv input. A workbook is a zip archive of xml parts; only as much of each format is
+// implemented as it takes to get at the cell values: stored and deflated zip entries, shared and
+// inline strings, and numbers, with date-formatted numbers written as `YYYY-MM-DD HH:MM:SS`.
+
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+fn invalid(message: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("not a readable xlsx workbook: {}", message))
+}
+
+pub fn first_sheet_as_csv(workbook: &[u8]) -> io::Result<Vec<u8>> {
+    let archive = Archive::new(workbook)?;
+    let workbook_xml = archive.text("xl/workbook.xml")?;
+    let (sheet_id, date1904) = first_sheet(&workbook_xml)?;
+    let sheet_path = sheet_path(&archive.text("xl/_rels/workbook.xml.rels")?, &sheet_id)?;
+    let shared_strings = match archive.has("xl/sharedStrings.xml") {
+        true => shared_strings(&archive.text("xl/sharedStrings.xml")?)?,
+        false => Vec::new(),
+    };
+    let date_styles = match archive.has("xl/styles.xml") {
+        true => date_styles(&archive.text("xl/styles.xml")?)?,
+        false => Vec::new(),
+    };
+
+    let epoch = match date1904 {
+        true => NaiveDate::from_ymd_opt(1904, 1, 1),
+        false => NaiveDate::from_ymd_opt(1899, 12, 30),
+    };
+    let cells = Cells { shared_strings, date_styles, epoch: epoch.unwrap().and_hms_opt(0, 0, 0).unwrap() };
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let mut width = None;
+    for row in cells.rows(&archive.text(&sheet_path)?) {
+        // Trailing empty cells aren't stored, so rows are padded out to the header's width. Rows
+        // that are only formatting, with no values, are left out.
+        let mut row = row?;
+        if row.iter().all(String::is_empty) {
+            continue;
+        }
+        let width = *width.get_or_insert(row.len());
+        if row.len() < width {
+            row.resize(width, String::new());
+        }
+        writer.write_record(&row).map_err(invalid)?;
+    }
+    writer.into_inner().map_err(|err| invalid(err.error()))
+}
+
+// The relationship id of the workbook's first sheet, and whether its dates count from 1904.
+fn first_sheet(workbook_xml: &str) -> io::Result<(String, bool)> {
+    let mut date1904 = false;
+    let mut xml = Xml::new(workbook_xml);
+    while let Some(event) = xml.next()? {
+        if let Event::Start(tag) = event {
+            match tag.name {
+                "workbookPr" => date1904 = matches!(tag.attribute("date1904").as_deref(), Some("1") | Some("true")),
+                "sheet" => return tag.attribute("id").map(|id| (id, date1904)).ok_or_else(|| invalid("sheet without a relationship id")),
+                _ => {},
+            }
+        }
+    }
+    Err(invalid("no sheets"))
+}
+
+// The archive path of the part with the given relationship id.
+fn sheet_path(relationships_xml: &str, id: &str) -> io::Result<String> {
+    let mut xml = Xml::new(relationships_xml);
+    while let Some(event) = xml.next()? {
+        if let Event::Start(tag) = event {
+            if tag.name == "Relationship" && tag.attribute("Id").as_deref() == Some(id) {
+                let target = tag.attribute("Target").ok_or_else(|| invalid("relationship without a target"))?;
+                return Ok(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            }
+        }
+    }
+    Err(invalid(format!("no part for sheet {}", id)))
+}
+
+fn shared_strings(shared_strings_xml: &str) -> io::Result<Vec<String>> {
+    let mut strings = Vec::new();
+    let mut xml = Xml::new(shared_strings_xml);
+    while let Some(event) = xml.next()? {
+        if let Event::Start(tag) = event {
+            if tag.name == "si" && !tag.empty {
+                strings.push(xml.string_item("si")?);
+            } else if tag.name == "si" {
+                strings.push(String::new());
+            }
+        }
+    }
+    Ok(strings)
+}
+
+// For each cell style, whether it formats numbers as dates or times.
+fn date_styles(styles_xml: &str) -> io::Result<Vec<bool>> {
+    let mut custom_formats = HashMap::new();
+    let mut date_styles = Vec::new();
+    let mut in_cell_formats = false;
+    let mut xml = Xml::new(styles_xml);
+    while let Some(event) = xml.next()? {
+        match event {
+            Event::Start(tag) if tag.name == "numFmt" => {
+                if let (Some(id), Some(code)) = (tag.attribute("numFmtId"), tag.attribute("formatCode")) {
+                    custom_formats.insert(id, code);
+                }
+            },
+            Event::Start(tag) if tag.name == "cellXfs" => in_cell_formats = !tag.empty,
+            Event::End("cellXfs") => in_cell_formats = false,
+            Event::Start(tag) if tag.name == "xf" && in_cell_formats => {
+                let id = tag.attribute("numFmtId").unwrap_or_default();
+                date_styles.push(match custom_formats.get(&id) {
+                    Some(code) => is_date_format(code),
+                    None => id.parse().map(is_builtin_date_format).unwrap_or(false),
+                });
+            },
+            _ => {},
+        }
+    }
+    Ok(date_styles)
+}
+
+fn is_builtin_date_format(id: u32) -> bool {
+    matches!(id, 14..=22 | 27..=36 | 45..=47 | 50..=58)
+}
+
+// Whether a custom number format shows a date or time, going by the letters left once quoted
+// text, escaped characters and bracketed colors and conditions are taken out.
+fn is_date_format(code: &str) -> bool {
+    let mut chars = code.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => while chars.next().is_some_and(|c| c != '"') {},
+            '[' => while chars.next().is_some_and(|c| c != ']') {},
+            '\\' | '_' | '*' => {
+                chars.next();
+            },
+            'd' | 'D' | 'm' | 'M' | 'y' | 'Y' | 'h' | 'H' | 's' | 'S' => return true,
+            _ => {},
+        }
+    }
+    false
+}
+
+struct Cells {
+    shared_strings: Vec<String>,
+    date_styles: Vec<bool>,
+    epoch: chrono::NaiveDateTime,
+}
+
+impl Cells {
+    fn rows<'a>(&'a self, sheet_xml: &'a str) -> impl Iterator<Item = io::Result<Vec<String>>> + 'a {
+        let mut xml = Xml::new(sheet_xml);
+        std::iter::from_fn(move || loop {
+            match xml.next() {
+                Ok(Some(Event::Start(tag))) if tag.name == "row" && !tag.empty => return Some(self.row(&mut xml)),
+                Ok(Some(_)) => {},
+                Ok(None) => return None,
+                Err(err) => return Some(Err(err)),
+            }
+        })
+    }
+
+    fn row(&self, xml: &mut Xml) -> io::Result<Vec<String>> {
+        let mut row = Vec::new();
+        while let Some(event) = xml.next()? {
+            match event {
+                Event::Start(tag) if tag.name == "c" => {
+                    // Empty cells aren't stored, so each cell goes to the column its reference names.
+                    let column = match tag.attribute("r") {
+                        Some(reference) => column_index(&reference)?,
+                        None => row.len(),
+                    };
+                    let value = match tag.empty {
+                        true => String::new(),
+                        false => self.cell(xml, &tag)?,
+                    };
+                    if row.len() <= column {
+                        row.resize(column + 1, String::new());
+                    }
+                    row[column] = value;
+                },
+                Event::End("row") => break,
+                _ => {},
+            }
+        }
+        Ok(row)
+    }
+
+    fn cell(&self, xml: &mut Xml, tag: &Tag) -> io::Result<String> {
+        let mut value = String::new();
+        while let Some(event) = xml.next()? {
+            match event {
+                Event::Start(inner) if inner.name == "v" && !inner.empty => value = xml.text("v")?,
+                Event::Start(inner) if inner.name == "is" && !inner.empty => value = xml.string_item("is")?,
+                Event::Start(inner) if !inner.empty => xml.skip(inner.name)?,
+                Event::End("c") => break,
+                _ => {},
+            }
+        }
+
+        match tag.attribute("t").as_deref() {
+            Some("s") => {
+                let index: usize = value.trim().parse().map_err(|_| invalid(format!("shared string index {:?}", value)))?;
+                self.shared_strings.get(index).cloned().ok_or_else(|| invalid(format!("no shared string {}", index)))
+            },
+            Some("b") => Ok(if value.trim() == "1" { "TRUE" } else { "FALSE" }.to_string()),
+            Some("str") | Some("inlineStr") | Some("e") => Ok(value),
+            _ => {
+                let style: usize = tag.attribute("s").and_then(|style| style.parse().ok()).unwrap_or(0);
+                if value.is_empty() || !self.date_styles.get(style).copied().unwrap_or(false) {
+                    return Ok(value);
+                }
+                let serial: f64 = value.trim().parse().map_err(|_| invalid(format!("date {:?}", value)))?;
+                let date_time = self.epoch + Duration::seconds((serial * 86_400.0).round() as i64);
+                Ok(date_time.format("%Y-%m-%d %H:%M:%S").to_string())
+            },
+        }
+    }
+}
+
+// The zero-based column of a cell reference like `AB12`.
+fn column_index(reference: &str) -> io::Result<usize> {
+    let letters: Vec<u8> = reference.bytes().take_while(|byte| byte.is_ascii_alphabetic()).collect();
+    if letters.is_empty() || letters.len() > 3 {
+        return Err(invalid(format!("cell reference {:?}", reference)));
+    }
+    Ok(letters.iter().fold(0, |index, letter| index * 26 + (letter.to_ascii_uppercase() - b'A') as usize + 1) - 1)
+}
+
+// The entries of a zip archive, found through its central directory.
+struct Archive<'a> {
+    data: &'a [u8],
+    entries: HashMap<String, Entry>,
+}
+
+struct Entry {
+    method: u16,
+    compressed_size: usize,
+    local_header: usize,
+}
+
+impl<'a> Archive<'a> {
+    fn new(data: &'a [u8]) -> io::Result<Self> {
+        // The end of central directory record is the last thing in the archive, though a comment
+        // of up to 64k may follow it.
+        let end = (0..data.len().saturating_sub(21)).rev().take(65_536 + 22)
+            .find(|&offset| read_u32(data, offset).ok() == Some(0x0605_4b50))
+            .ok_or_else(|| invalid("not a zip archive"))?;
+        let count = read_u16(data, end + 10)? as usize;
+        let mut offset = read_u32(data, end + 16)? as usize;
+
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if read_u32(data, offset)? != 0x0201_4b50 {
+                return Err(invalid("corrupt zip directory"));
+            }
+            let name_length = read_u16(data, offset + 28)? as usize;
+            let extra_length = read_u16(data, offset + 30)? as usize;
+            let comment_length = read_u16(data, offset + 32)? as usize;
+            let name = data.get(offset + 46..offset + 46 + name_length).ok_or_else(|| invalid("truncated zip directory"))?;
+            entries.insert(String::from_utf8_lossy(name).into_owned(), Entry {
+                method: read_u16(data, offset + 10)?,
+                compressed_size: read_u32(data, offset + 20)? as usize,
+                local_header: read_u32(data, offset + 42)? as usize,
+            });
+            offset += 46 + name_length + extra_length + comment_length;
+        }
+        Ok(Archive { data, entries })
+    }
+
+    fn has(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    fn text(&self, name: &str) -> io::Result<String> {
+        let entry = self.entries.get(name).ok_or_else(|| invalid(format!("no {}", name)))?;
+        if read_u32(self.data, entry.local_header)? != 0x0403_4b50 {
+            return Err(invalid(format!("corrupt zip entry {}", name)));
+        }
+        let start = entry.local_header + 30 + read_u16(self.data, entry.local_header + 26)? as usize + read_u16(self.data, entry.local_header + 28)? as usize;
+        let contents = self.data.get(start..start + entry.compressed_size).ok_or_else(|| invalid(format!("truncated zip entry {}", name)))?;
+        let contents = match entry.method {
+            0 => contents.to_vec(),
+            8 => inflate(contents)?,
+            method => return Err(invalid(format!("{} uses unsupported compression method {}", name, method))),
+        };
+        String::from_utf8(contents).map_err(|_| invalid(format!("{} is not utf-8", name)))
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> io::Result<u16> {
+    match data.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+        None => Err(invalid("truncated zip archive")),
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> io::Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(invalid("truncated zip archive")),
+    }
+}
+
+// Decompresses raw deflate data (RFC 1951).
+fn inflate(data: &[u8]) -> io::Result<Vec<u8>> {
+    const LENGTH_BASES: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+    const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+    const DISTANCE_BASES: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+    const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+
+    let mut bits = Bits { data, position: 0 };
+    let mut output = Vec::new();
+    loop {
+        let last = bits.read(1)? == 1;
+        let (literals, distances) = match bits.read(2)? {
+            0 => {
+                bits.position = bits.position.div_ceil(8) * 8;
+                let start = bits.position / 8;
+                let length = read_u16(data, start)? as usize;
+                let stored = data.get(start + 4..start + 4 + length).ok_or_else(|| invalid("truncated deflate block"))?;
+                output.extend_from_slice(stored);
+                bits.position = (start + 4 + length) * 8;
+                if last {
+                    return Ok(output);
+                }
+                continue;
+            },
+            1 => {
+                let mut lengths = [8; 288];
+                lengths[144..256].iter_mut().for_each(|length| *length = 9);
+                lengths[256..280].iter_mut().for_each(|length| *length = 7);
+                (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+            },
+            2 => {
+                const ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+                let literal_count = bits.read(5)? as usize + 257;
+                let distance_count = bits.read(5)? as usize + 1;
+                let code_length_count = bits.read(4)? as usize + 4;
+                let mut code_lengths = [0; 19];
+                for &symbol in &ORDER[..code_length_count] {
+                    code_lengths[symbol] = bits.read(3)? as u8;
+                }
+                let code_lengths = Huffman::new(&code_lengths);
+                let mut lengths = Vec::with_capacity(literal_count + distance_count);
+                while lengths.len() < literal_count + distance_count {
+                    let (value, repeat) = match code_lengths.decode(&mut bits)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => (*lengths.last().ok_or_else(|| invalid("repeated code length with nothing before it"))?, 3 + bits.read(2)?),
+                        17 => (0, 3 + bits.read(3)?),
+                        _ => (0, 11 + bits.read(7)?),
+                    };
+                    lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                }
+                if lengths.len() > literal_count + distance_count {
+                    return Err(invalid("code lengths overrun"));
+                }
+                (Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..]))
+            },
+            _ => return Err(invalid("reserved deflate block type")),
+        };
+
+        loop {
+            match literals.decode(&mut bits)? {
+                symbol @ 0..=255 => output.push(symbol as u8),
+                256 => break,
+                symbol => {
+                    let index = symbol as usize - 257;
+                    let length = *LENGTH_BASES.get(index).ok_or_else(|| invalid("bad length code"))? as usize + bits.read(LENGTH_EXTRA_BITS[index])? as usize;
+                    let index = distances.decode(&mut bits)? as usize;
+                    let distance = *DISTANCE_BASES.get(index).ok_or_else(|| invalid("bad distance code"))? as usize + bits.read(DISTANCE_EXTRA_BITS[index])? as usize;
+                    if distance > output.len() {
+                        return Err(invalid("distance before the start of the data"));
+                    }
+                    // The copy may overlap what it is writing, so it goes a byte at a time.
+                    let start = output.len() - distance;
+                    for offset in 0..length {
+                        output.push(output[start + offset]);
+                    }
+                },
+            }
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+// Reads deflate's bit stream, least significant bit first.
+struct Bits<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl Bits<'_> {
+    fn read(&mut self, count: u8) -> io::Result<u32> {
+        let mut value = 0;
+        for bit in 0..count {
+            let byte = self.data.get(self.position / 8).ok_or_else(|| invalid("truncated deflate data"))?;
+            value |= (((byte >> (self.position % 8)) & 1) as u32) << bit;
+            self.position += 1;
+        }
+        Ok(value)
+    }
+}
+
+// A canonical Huffman code, given the code length of each symbol, decoded a bit at a time.
+struct Huffman {
+    counts: [u16; 16], // the number of codes of each length.
+    symbols: Vec<u16>, // the symbols ordered by code.
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16).filter(|&symbol| lengths[symbol as usize] != 0).collect();
+        symbols.sort_by_key(|&symbol| lengths[symbol as usize]);
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= bits.read(1)? as usize;
+            if code < first + count as usize {
+                return Ok(self.symbols[index + code - first]);
+            }
+            index += count as usize;
+            first = (first + count as usize) << 1;
+            code <<= 1;
+        }
+        Err(invalid("bad huffman code"))
+    }
+}
+
+// Just enough of an xml reader for workbook parts: elements with their attributes and text, with
+// namespace prefixes dropped. Comments, processing instructions and doctypes are skipped.
+struct Xml<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+enum Event<'a> {
+    Start(Tag<'a>),
+    End(&'a str),
+    Text(String),
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    attributes: &'a str,
+    empty: bool, // a self-closing tag, which has no matching end.
+}
+
+impl Tag<'_> {
+    fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes;
+        while let Some(equals) = rest.find('=') {
+            let key = local_name(rest[..equals].trim());
+            let value = rest[equals + 1..].trim_start();
+            let quote = value.chars().next()?;
+            let end = value[1..].find(quote)? + 1;
+            if key == name {
+                return Some(unescape(&value[1..end]));
+            }
+            rest = &value[end + 1..];
+        }
+        None
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+impl<'a> Xml<'a> {
+    fn new(text: &'a str) -> Self {
+        Xml { text, position: 0 }
+    }
+
+    fn next(&mut self) -> io::Result<Option<Event<'a>>> {
+        loop {
+            let rest = &self.text[self.position..];
+            if rest.is_empty() {
+                return Ok(None);
+            }
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.position += end;
+                return Ok(Some(Event::Text(unescape(&rest[..end]))));
+            }
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let end = cdata.find("]]>").ok_or_else(|| invalid("unterminated CDATA"))?;
+                self.position += 9 + end + 3;
+                return Ok(Some(Event::Text(cdata[..end].to_string())));
+            }
+            let (terminator, skipped) = match () {
+                _ if rest.starts_with("<!--") => ("-->", true),
+                _ if rest.starts_with("<?") => ("?>", true),
+                _ if rest.starts_with("<!") => (">", true),
+                _ => (">", false),
+            };
+            let end = rest.find(terminator).ok_or_else(|| invalid("unterminated tag"))?;
+            self.position += end + terminator.len();
+            if skipped {
+                continue;
+            }
+
+            let tag = &rest[1..end];
+            if let Some(name) = tag.strip_prefix('/') {
+                return Ok(Some(Event::End(local_name(name.trim()))));
+            }
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+            return Ok(Some(Event::Start(Tag { name: local_name(&tag[..name_end]), attributes: &tag[name_end..], empty })));
+        }
+    }
+
+    // The text up to the end of the named element, which has just started.
+    fn text(&mut self, name: &str) -> io::Result<String> {
+        let mut text = String::new();
+        while let Some(event) = self.next()? {
+            match event {
+                Event::Text(part) => text.push_str(&part),
+                Event::End(end) if end == name => return Ok(text),
+                _ => {},
+            }
+        }
+        Err(invalid(format!("unterminated {}", name)))
+    }
+
+    // The text of a string item (a shared string or an inline one): its plain or rich text runs,
+    // without any phonetic guides.
+    fn string_item(&mut self, name: &str) -> io::Result<String> {
+        let mut text = String::new();
+        while let Some(event) = self.next()? {
+            match event {
+                Event::Start(tag) if tag.name == "t" && !tag.empty => text.push_str(&self.text("t")?),
+                Event::Start(tag) if tag.name == "rPh" && !tag.empty => self.skip("rPh")?,
+                Event::End(end) if end == name => return Ok(text),
+                _ => {},
+            }
+        }
+        Err(invalid(format!("unterminated {}", name)))
+    }
+
+    // Skips past the end of the named element, which has just started.
+    fn skip(&mut self, name: &str) -> io::Result<()> {
+        let mut depth = 0;
+        while let Some(event) = self.next()? {
+            match event {
+                Event::Start(tag) if tag.name == name && !tag.empty => depth += 1,
+                Event::End(end) if end == name => {
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                    depth -= 1;
+                },
+                _ => {},
+            }
+        }
+        Err(invalid(format!("unterminated {}", name)))
+    }
+}
+
+fn unescape(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()).and_then(char::from_u32),
+            },
+        };
+        match character {
+            Some(character) => unescaped.push(character),
+            None => unescaped.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
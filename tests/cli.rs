@@ -0,0 +1,914 @@
+// End-to-end tests of the command line: each runs the binary over a small input written to its
+// own scratch directory and checks the files it writes.
+
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// An empty directory for a single test, named after it so that tests can run in parallel.
+fn scratch_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sensor-data-{}-{}", std::process::id(), test));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn run(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sensor-data")).current_dir(dir).args(args).output().unwrap()
+}
+
+// Summarizes `input` with the extra `args` and returns the run and the written summary.
+fn summarize(test: &str, input: &str, args: &[&str]) -> (Output, String) {
+    let dir = scratch_dir(test);
+    fs::write(dir.join("input.csv"), input).unwrap();
+    let mut all_args = vec!["--input", "input.csv", "--output", "out.csv"];
+    all_args.extend(args);
+    let output = run(&dir, &all_args);
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap_or_default();
+    (output, summary)
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+const HEADER: &str = "timestamp,temperature,humidity,dewpoint,vpd\n";
+
+#[test]
+fn freeze_column_is_only_written_when_asked_for() {
+    let input = format!("{}2021-01-01 06:00:00,0.0,80,-2,0.1\n2021-01-02 06:00:00,0.5,80,-2,0.1\n", HEADER);
+    let (output, summary) = summarize("freeze-default", &input, &["--celsius"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!summary.lines().next().unwrap().contains("freeze"));
+
+    let (output, summary) = summarize("freeze-column", &input, &["--celsius", "--freeze-column"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let mut reader = csv::Reader::from_reader(summary.as_bytes());
+    let freeze = reader.headers().unwrap().iter().position(|name| name == "freeze").unwrap();
+    let freeze_days: Vec<String> = reader.records().map(|record| record.unwrap()[freeze].to_string()).collect();
+    assert_eq!(freeze_days, vec!["true", "false"]);
+}
+
+#[test]
+fn future_dated_records_are_flagged_and_dropped_with_drop_future() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2099-01-01 06:00:00,61,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("future-flagged", &input, &["--max-date", "2021-06-01"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(summary.lines().count(), 3);
+    assert!(stderr(&output).contains("warning: 1 records dated after 2021-06-01\n"));
+
+    let (output, summary) = summarize("future-dropped", &input, &["--max-date", "2021-06-01", "--drop-future"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(summary.lines().count(), 2);
+    assert!(stderr(&output).contains("warning: 1 records dated after 2021-06-01 were dropped"));
+}
+
+// The values of the named column of a csv summary, one per row.
+fn column(summary: &str, name: &str) -> Vec<String> {
+    let mut reader = csv::Reader::from_reader(summary.as_bytes());
+    let index = reader.headers().unwrap().iter().position(|header| header == name).unwrap_or_else(|| panic!("no {:?} column in {}", name, summary));
+    reader.records().map(|record| record.unwrap()[index].to_string()).collect()
+}
+
+#[test]
+fn degree_days_column_accumulates_the_capped_directional_values() {
+    let input = format!("{}2021-07-01 06:00:00,40,50,40,1.0\n2021-07-01 15:00:00,95,50,40,1.0\n2021-07-02 06:00:00,30,50,40,1.0\n2021-07-02 15:00:00,60,50,40,1.0\n", HEADER);
+    let (output, summary) = summarize("degree-days", &input, &["--degree-day-base", "65", "--degree-day-direction", "below", "--degree-day-cap-low", "50", "--degree-day-cap-high", "86"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "degree days"), vec!["0", "10"]);
+}
+
+#[test]
+fn appending_a_day_keeps_a_single_header() {
+    let dir = scratch_dir("append");
+    fs::write(dir.join("input.csv"), format!("{}2021-01-01 06:00:00,60,40,35,1.0\n", HEADER)).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    fs::write(dir.join("input.csv"), format!("{}2021-01-02 06:00:00,62,40,35,1.0\n", HEADER)).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--append"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    assert_eq!(summary.lines().filter(|line| line.starts_with("date,")).count(), 1, "{}", summary);
+    assert_eq!(column(&summary, "date"), vec!["2021-01-01", "2021-01-02"]);
+}
+
+#[test]
+fn explain_shows_the_base_extremes_and_gdd_of_the_day() {
+    let input = format!("{}2021-06-01 06:00:00,60,40,35,1.0\n2021-06-01 15:00:00,80,40,35,1.0\n2021-06-02 06:00:00,70,40,35,1.0\n", HEADER);
+    let (output, _) = summarize("explain", &input, &["--explain", "2021-06-01", "--gdd-base", "50"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let explanation = String::from_utf8_lossy(&output.stdout);
+    for line in &["GDD for 2021-06-01", "  max temperature: 80", "  min temperature: 60", "  mean temperature: 70", "  base: 50", "  gdd: 70 - 50 = 20"] {
+        assert!(explanation.lines().any(|explained| explained == *line), "no {:?} in {}", line, explanation);
+    }
+}
+
+#[test]
+fn report_errors_reports_every_bad_row_and_splits_the_file() {
+    let input = format!("{}{}{}{}{}{}",
+        HEADER,
+        "2021-01-01 06:00:00,60,40,35,1.0\n",
+        "2021-01-01 07:00:00,warm,40,35,1.0\n",
+        "2021-13-01 08:00:00,61,40,35,1.0\n",
+        "2021-01-01 09:00:00,62,40\n",
+        "2021-01-01 10:00:00,63,40,35,1.0\n",
+    );
+    let dir = scratch_dir("report-errors");
+    fs::write(dir.join("input.csv"), input).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--report-errors", "--clean-output", "clean.csv", "--rejects-output", "rejects.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let errors = stderr(&output);
+    assert!(errors.contains("3 rows rejected:\n"), "{}", errors);
+    for error in &["line 3: invalid temperature: \"warm\"", "line 4: invalid date: \"2021-13-01 08:00:00\"", "line 5: expected 5 fields, found 3"] {
+        assert!(errors.contains(error), "no {:?} in {}", error, errors);
+    }
+    let clean = fs::read_to_string(dir.join("clean.csv")).unwrap();
+    assert_eq!(column(&clean, "timestamp"), vec!["2021-01-01 06:00:00", "2021-01-01 10:00:00"]);
+    let rejects = fs::read_to_string(dir.join("rejects.csv")).unwrap();
+    assert_eq!(rejects.lines().count(), 4, "{}", rejects);
+}
+
+#[test]
+fn pivot_transposes_metrics_into_rows_and_dates_into_columns() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,45,35,1.0\n2021-01-03 06:00:00,64,50,35,1.0\n", HEADER);
+    let (output, unpivoted) = summarize("unpivoted", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let (output, pivoted) = summarize("pivot", &input, &["--pivot"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let header: Vec<&str> = unpivoted.lines().next().unwrap().split(',').collect();
+    let rows: Vec<Vec<&str>> = pivoted.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), header.len());
+    assert!(rows.iter().all(|row| row.len() == 4));
+    assert_eq!(rows[0], vec!["date", "2021-01-01", "2021-01-02", "2021-01-03"]);
+    assert_eq!(rows.iter().map(|row| row[0]).collect::<Vec<&str>>(), header);
+    let humidity = rows.iter().find(|row| row[0] == "avg humidity").unwrap();
+    assert_eq!(humidity[1..], ["40", "45", "50"]);
+}
+
+#[test]
+fn units_row_is_read_as_units_rather_than_data() {
+    let input = format!("{}time,°C,%,°C,kPa\n2021-01-01 06:00:00,20,40,6,1.4\n2021-01-01 18:00:00,24,40,6,1.8\n", HEADER);
+    let (output, summary) = summarize("units-row", &input, &["--units-row", "--freeze-column"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg temp"), vec!["22"]);
+    assert_eq!(column(&summary, "freeze"), vec!["false"]);
+}
+
+#[test]
+fn derive_vpd_warns_about_the_readings_it_clamped() {
+    let input = format!("{}2021-01-01 06:00:00,60,105,60,0.5\n2021-01-01 18:00:00,60,50,41,0.5\n", HEADER);
+    let (output, _) = summarize("derive-vpd", &input, &["--derive-vpd"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("warning: 1 readings had relative humidity above 100%; their derived vpd was clamped to 0"), "{}", stderr(&output));
+}
+
+// The keys of a flat JSON object, or None when `json` isn't one: every value must be a string,
+// a number, a boolean or null.
+fn json_object_keys(json: &str) -> Option<Vec<String>> {
+    fn string(rest: &mut &str) -> Option<String> {
+        *rest = rest.strip_prefix('"')?;
+        let mut value = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    *rest = &rest[index + 1..];
+                    return Some(value);
+                },
+                '\\' => value.push(chars.next()?.1),
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    let mut rest = json.trim().strip_prefix('{')?;
+    let mut keys = Vec::new();
+    while !rest.starts_with('}') {
+        if !keys.is_empty() {
+            rest = rest.strip_prefix(',')?;
+        }
+        keys.push(string(&mut rest)?);
+        rest = rest.strip_prefix(':')?;
+        if rest.starts_with('"') {
+            string(&mut rest)?;
+        } else {
+            let end = rest.find([',', '}'])?;
+            let value = &rest[..end];
+            if !["true", "false", "null"].contains(&value) && value.parse::<f64>().map_or(true, |number| !number.is_finite()) {
+                return None;
+            }
+            rest = &rest[end..];
+        }
+    }
+    if rest == "}" { Some(keys) } else { None }
+}
+
+#[test]
+fn each_json_line_is_a_standalone_object_with_the_summary_keys() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER);
+    let (output, json_lines) = summarize("json-lines", &input, &["--format", "jsonl"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let (output, csv) = summarize("json-lines-csv", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let expected_keys: Vec<String> = csv.lines().next().unwrap().split(',').map(|name| name.replace(' ', "_")).collect();
+    assert_eq!(json_lines.lines().count(), 2);
+    for line in json_lines.lines() {
+        assert_eq!(json_object_keys(line).as_ref(), Some(&expected_keys), "{}", line);
+    }
+    assert!(json_object_keys("{\"date\":\"2021-01-01\",\"avg_temp\":NaN}").is_none());
+}
+
+#[test]
+fn a_truncated_file_summarizes_its_complete_rows_when_tolerated() {
+    // Cut off partway through the final record.
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,70,60,45,2.0\n2021-01-03 06:00:00,6", HEADER);
+    let (output, _) = summarize("truncated", &input, &[]);
+    assert!(!output.status.success());
+
+    let (output, summary) = summarize("truncated-tolerated", &input, &["--tolerate-truncation"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "date"), vec!["2021-01-01", "2021-01-02"]);
+    assert_eq!(column(&summary, "avg temp"), vec!["60", "70"]);
+    assert!(stderr(&output).contains("warning: the sensor data ended unexpectedly; the incomplete final record was skipped"), "{}", stderr(&output));
+}
+
+#[test]
+fn events_come_from_the_events_flag() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("events-absent", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(summary.lines().skip(1).all(|line| line.ends_with(',')));
+
+    let dir = scratch_dir("events-given");
+    fs::write(dir.join("input.csv"), &input).unwrap();
+    fs::write(dir.join("events.csv"), "timestamp,event\n2021-01-02 08:00:00,planted\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--events", "events.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    let events: Vec<&str> = summary.lines().skip(1).map(|line| line.rsplit(',').next().unwrap()).collect();
+    assert_eq!(events, vec!["", "planted"]);
+}
+
+#[test]
+fn annotations_are_comment_lines_above_the_rows_of_their_days() {
+    let dir = scratch_dir("annotations");
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n2021-01-03 06:00:00,64,40,35,1.0\n", HEADER);
+    fs::write(dir.join("input.csv"), input).unwrap();
+    fs::write(dir.join("notes.csv"), "timestamp,note\n2021-01-02 09:00:00,sensor replaced\n2021-01-03 10:00:00,moved to bench 2\n2021-01-03 16:00:00,watered\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--annotations", "notes.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    let lines: Vec<&str> = summary.lines().map(|line| line.split(',').next().unwrap()).collect();
+    assert_eq!(lines, vec![
+        "date",
+        "2021-01-01",
+        "# 2021-01-02: sensor replaced",
+        "2021-01-02",
+        "# 2021-01-03: moved to bench 2",
+        "# 2021-01-03: watered",
+        "2021-01-03",
+    ]);
+
+    let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(summary.as_bytes());
+    let dates: Vec<String> = reader.records().map(|record| record.unwrap()[0].to_string()).collect();
+    assert_eq!(dates, vec!["2021-01-01", "2021-01-02", "2021-01-03"]);
+}
+
+#[test]
+fn custom_metrics_get_their_own_columns() {
+    let input = "timestamp,temperature,humidity,dewpoint,vpd,co2\n2021-01-01 06:00:00,60,40,35,1.0,400\n2021-01-01 18:00:00,70,40,35,1.0,500\n";
+    let (output, summary) = summarize("custom-metric", input, &["--metric", "co2:6:ppm"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let mut lines = summary.lines();
+    assert!(lines.next().unwrap().ends_with(",avg co2,max co2,min co2,event"));
+    assert!(lines.next().unwrap().ends_with(",450,500,400,"));
+}
+
+#[test]
+fn quote_style_controls_how_an_event_with_a_comma_is_quoted() {
+    let dir = scratch_dir("quote-style");
+    fs::write(dir.join("input.csv"), format!("{}2021-01-01 06:00:00,60,40,35,1.0\n", HEADER)).unwrap();
+    fs::write(dir.join("events.csv"), "timestamp,event\n2021-01-01 08:00:00,\"planted, watered\"\n").unwrap();
+    for (quote_style, row) in &[
+        ("necessary", "2021-01-01,60,60,60,40,40,40,35,1,-5,\"planted, watered\""),
+        ("always", "\"2021-01-01\",\"60\",\"60\",\"60\",\"40\",\"40\",\"40\",\"35\",\"1\",\"-5\",\"planted, watered\""),
+        ("never", "2021-01-01,60,60,60,40,40,40,35,1,-5,planted, watered"),
+    ] {
+        let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--events", "events.csv", "--quote-style", quote_style]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+        assert_eq!(summary.lines().nth(1), Some(*row), "--quote-style {}", quote_style);
+    }
+}
+
+#[test]
+fn generating_a_seeded_week_is_deterministic() {
+    let dir = scratch_dir("generate");
+    let generate = |seed: &str| {
+        let output = run(&dir, &["generate", "--start", "2021-06-01", "--days", "7", "--interval", "1h", "--seed", seed]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let data = generate("42");
+    assert_eq!(data, generate("42"));
+    assert_ne!(data, generate("43"));
+    let lines: Vec<&str> = data.lines().collect();
+    assert_eq!(lines.len(), 1 + 7 * 24);
+    assert!(lines[1].starts_with("2021-06-01 00:00:00,"));
+    assert!(lines[lines.len() - 1].starts_with("2021-06-07 23:00:00,"));
+
+    // The generated data is valid input.
+    fs::write(dir.join("input.csv"), &data).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(dir.join("out.csv")).unwrap().lines().count(), 1 + 7);
+
+    // A unit that isn't one byte long is refused rather than split in the middle of a character.
+    let output = run(&dir, &["generate", "--start", "2021-06-01", "--days", "7", "--interval", "15µ"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("Invalid value for --interval: 15µ"), "{}", stderr(&output));
+}
+
+#[test]
+fn cold_days_that_lower_the_cumulative_gdd_are_warned_about() {
+    let warm = format!("{}2021-05-01 06:00:00,70,40,35,1.0\n2021-05-01 18:00:00,80,40,35,1.0\n", HEADER);
+    let (output, _) = summarize("gdd-increases", &warm, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("cumulative gdd decreases"), "{}", stderr(&output));
+
+    let cold = format!("{}2021-05-02 06:00:00,40,40,35,1.0\n2021-05-03 06:00:00,50,40,35,1.0\n", warm);
+    let (output, summary) = summarize("gdd-decreases", &cold, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // The gdd column is the running total, which drops with each day below the base.
+    assert_eq!(column(&summary, "gdd"), vec!["10", "-15", "-30"]);
+    assert!(stderr(&output).contains("warning: cumulative gdd decreases below zero on 2 days, first on 2021-05-02; check that the gdd base (65) suits the data"), "{}", stderr(&output));
+
+    // A cool day that leaves some of the heat, or a cold first day, is to be expected.
+    let cool = format!("{}2021-05-02 06:00:00,60,40,35,1.0\n", warm);
+    let cold_start = format!("{}2021-05-01 06:00:00,40,40,35,1.0\n", HEADER);
+    for (test, input) in [("gdd-cool-day", &cool), ("gdd-cold-start", &cold_start)] {
+        let (output, _) = summarize(test, input, &[]);
+        assert!(!stderr(&output).contains("cumulative gdd decreases"), "{}", stderr(&output));
+    }
+
+    // A reset event starts the running total over, so the cold day it falls on isn't a decrease.
+    let dir = scratch_dir("gdd-reset");
+    fs::write(dir.join("input.csv"), format!("{}2021-05-02 06:00:00,60,40,35,1.0\n2021-05-03 06:00:00,70,40,35,1.0\n", warm)).unwrap();
+    fs::write(dir.join("events.csv"), "timestamp,event\n2021-05-02 00:00:00,reset\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--events", "events.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&fs::read_to_string(dir.join("out.csv")).unwrap(), "gdd"), vec!["10", "-5", "0"]);
+    assert!(!stderr(&output).contains("cumulative gdd decreases"), "{}", stderr(&output));
+}
+
+#[cfg(feature = "xlsx")]
+#[test]
+fn an_xlsx_workbook_is_summarized_like_its_csv_export() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let dir = scratch_dir("xlsx");
+    let mut summaries = Vec::new();
+    for input in &["readings.csv", "readings.xlsx"] {
+        let output = run(&dir, &["--input", fixtures.join(input).to_str().unwrap(), "--output", "out.csv"]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        summaries.push(fs::read_to_string(dir.join("out.csv")).unwrap());
+    }
+    assert_eq!(summaries[0].lines().count(), 1 + 3);
+    assert_eq!(summaries[0], summaries[1]);
+}
+
+#[cfg(not(feature = "xlsx"))]
+#[test]
+fn xlsx_input_without_the_feature_is_refused() {
+    let (output, _) = summarize("xlsx-refused", "", &["--input", "readings.xlsx"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("needs the xlsx feature"), "{}", stderr(&output));
+}
+
+#[test]
+fn date_output_rfc3339_writes_each_date_as_midnight_utc() {
+    let input = format!("{}2021-03-09 06:00:00,60,40,35,1.0\n2021-03-10 06:00:00,62,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("date-output-rfc3339", &input, &["--date-output", "rfc3339"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "date"), vec!["2021-03-09T00:00:00Z", "2021-03-10T00:00:00Z"]);
+
+    let (output, summary) = summarize("date-output-naive", &input, &["--date-output", "naive"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "date"), vec!["2021-03-09", "2021-03-10"]);
+}
+
+#[test]
+fn apparent_temperature_column_is_only_written_with_a_wind_speed_column() {
+    let input = "timestamp,temperature,humidity,dewpoint,vpd,wind speed\n2021-01-01 06:00:00,30,60,18,0.2,2\n";
+    let (output, summary) = summarize("apparent-temperature", input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg apparent temp"), vec!["30"]);
+
+    let (output, summary) = summarize("no-apparent-temperature", &format!("{}2021-01-01 06:00:00,30,60,18,0.2\n", HEADER), &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!summary.lines().next().unwrap().contains("apparent"), "{}", summary);
+}
+
+#[test]
+fn a_tiny_vpd_is_written_in_fixed_point() {
+    let input = format!("{}2021-01-01 06:00:00,60,99.99,59.9,0.0000001\n", HEADER);
+    let (output, summary) = summarize("tiny-vpd", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg vpd"), vec!["0.0000001"]);
+
+    let (output, summary) = summarize("tiny-vpd-rounded", &input, &["--round", "3"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg vpd"), vec!["0.000"]);
+}
+
+#[test]
+fn group_by_summarizes_each_label_separately() {
+    let input = "timestamp,temperature,humidity,dewpoint,vpd,treatment\n\
+        2021-01-01 06:00:00,60,40,35,1.0,B\n\
+        2021-01-01 07:00:00,70,40,35,1.0,A\n\
+        2021-01-01 18:00:00,64,40,35,1.0,B\n\
+        2021-01-02 06:00:00,72,40,35,1.0,A\n";
+    let (output, summary) = summarize("group-by", input, &["--group-by", "treatment"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // Groups come in the order their labels first appear.
+    assert_eq!(column(&summary, "group"), vec!["B", "A", "A"]);
+    assert_eq!(column(&summary, "date"), vec!["2021-01-01", "2021-01-01", "2021-01-02"]);
+    assert_eq!(column(&summary, "avg temp"), vec!["62", "70", "72"]);
+}
+
+#[test]
+fn gdd_both_units_writes_fahrenheit_and_celsius_degree_days() {
+    // A mean of 83 °F against the base of 65 is 18 F-days, which is 10 C-days.
+    let input = format!("{}2021-06-01 06:00:00,78,40,35,1.0\n2021-06-01 18:00:00,88,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("gdd-both-units", &input, &["--gdd-both-units"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "gdd f-days"), vec!["18"]);
+    assert_eq!(column(&summary, "gdd c-days"), vec!["10"]);
+}
+
+#[test]
+fn metrics_only_writes_numeric_columns_and_an_aligned_index() {
+    let dir = scratch_dir("metrics-only");
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n2021-01-03 06:00:00,64,40,35,1.0\n", HEADER);
+    fs::write(dir.join("input.csv"), input).unwrap();
+    fs::write(dir.join("events.csv"), "timestamp,event\n2021-01-02 08:00:00,planted\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--events", "events.csv", "--metrics-only", "--index-output", "index.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    let header = summary.lines().next().unwrap();
+    assert!(!header.split(',').any(|name| name == "date" || name == "event"), "{}", header);
+    assert!(summary.lines().skip(1).all(|line| line.split(',').all(|value| value.parse::<f32>().is_ok())), "{}", summary);
+    assert_eq!(column(&summary, "avg temp"), vec!["60", "62", "64"]);
+
+    let index = fs::read_to_string(dir.join("index.csv")).unwrap();
+    assert_eq!(index, "row,date\n0,2021-01-01\n1,2021-01-02\n2,2021-01-03\n");
+}
+
+#[test]
+fn no_output_header_leaves_out_only_the_header() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER);
+    let (output, with_header) = summarize("with-output-header", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let (output, without_header) = summarize("no-output-header", &input, &["--no-output-header"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    assert!(with_header.starts_with("date,"));
+    assert_eq!(without_header.lines().collect::<Vec<_>>(), with_header.lines().skip(1).collect::<Vec<_>>());
+}
+
+#[test]
+fn event_agg_picks_how_three_same_day_events_are_written() {
+    let dir = scratch_dir("event-agg");
+    fs::write(dir.join("input.csv"), format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER)).unwrap();
+    fs::write(dir.join("events.csv"), "timestamp,event\n2021-01-02 08:00:00,planted\n2021-01-02 09:00:00,watered\n2021-01-02 17:00:00,fertilized\n").unwrap();
+    for (event_agg, events) in &[
+        ("first", vec!["", "planted"]),
+        ("last", vec!["", "fertilized"]),
+        ("all", vec!["", "planted; watered; fertilized"]),
+        ("count", vec!["0", "3"]),
+    ] {
+        let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--events", "events.csv", "--event-agg", event_agg]);
+        assert!(output.status.success(), "{}", stderr(&output));
+        let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+        assert_eq!(&column(&summary, "event"), events, "--event-agg {}", event_agg);
+    }
+}
+
+#[test]
+fn fail_on_warning_exits_with_the_warning_count_and_succeeds_without_it() {
+    let input = format!("{}2021-01-01 06:00:00,70,40,35,1.0\n2099-01-01 06:00:00,72,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("fail-on-warning", &input, &["--max-date", "2021-06-01", "--fail-on-warning"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).ends_with("Failing on 1 data quality warning(s) (--fail-on-warning).\n"), "{}", stderr(&output));
+    assert_eq!(summary, "");
+
+    // The same warning only fails the run when asked to.
+    let (output, summary) = summarize("no-fail-on-warning", &input, &["--max-date", "2021-06-01"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("warning: 1 records dated after 2021-06-01"), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg temp"), vec!["70", "72"]);
+}
+
+#[test]
+fn compare_and_generate_errors_are_reported_by_their_message() {
+    let dir = scratch_dir("command-errors");
+    for args in [&["--output", "missing.csv", "--compare", "other.csv"][..], &["generate", "--start", "2021-06-01", "--days", "1", "--output", "missing/data.csv"]] {
+        let output = run(&dir, args);
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr(&output).starts_with("Error reading input: "), "{}", stderr(&output));
+        assert!(!stderr(&output).contains("Os {"), "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn median_columns_are_written_with_the_computed_medians() {
+    let input = format!("{}2021-01-01 06:00:00,60,40,30,0.5\n2021-01-01 12:00:00,61,70,35,1.0\n2021-01-01 18:00:00,80,50,45,2.5\n", HEADER);
+    let (output, summary) = summarize("no-median", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!summary.lines().next().unwrap().contains("median"), "{}", summary);
+
+    let (output, summary) = summarize("median", &input, &["--median"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "median temp"), vec!["61"]);
+    assert_eq!(column(&summary, "median humidity"), vec!["50"]);
+    assert_eq!(column(&summary, "median dewpoint"), vec!["35"]);
+    assert_eq!(column(&summary, "median vpd"), vec!["1"]);
+    // Unlike the means, which the outlying readings pull away.
+    assert_eq!(column(&summary, "avg temp"), vec!["67"]);
+}
+
+#[test]
+fn a_calibration_offset_corrects_the_temperature_means() {
+    let dir = scratch_dir("calibration");
+    fs::write(dir.join("input.csv"), format!("{}2021-06-01 06:00:00,15,60,8,0.7\n2021-06-01 18:00:00,25,50,14,1.6\n", HEADER)).unwrap();
+    fs::write(dir.join("calibration.csv"), "metric,offset\ntemperature,1.0\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--celsius", "--calibration", "calibration.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("calibrating temperature: × 1 +1\n"));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("calibrating"));
+
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    assert_eq!(column(&summary, "avg temp"), vec!["21"]);
+    assert_eq!((column(&summary, "max temp"), column(&summary, "min temp")), (vec!["26".to_string()], vec!["16".to_string()]));
+    assert_eq!(column(&summary, "avg humidity"), vec!["55"]);
+}
+
+#[test]
+fn normalized_daily_means_have_zero_mean_and_unit_std() {
+    let input = format!(
+        "{}2021-01-01 06:00:00,60,40,30,0.5\n2021-01-02 06:00:00,70,50,35,1.0\n2021-01-03 06:00:00,65,80,45,2.5\n2021-01-04 06:00:00,81,45,45,2.5\n",
+        HEADER,
+    );
+    let (output, summary) = summarize("normalize", &input, &["--normalize"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    for name in &["avg temp z", "avg humidity z", "avg dewpoint z", "avg vpd z"] {
+        let z_scores: Vec<f64> = column(&summary, name).iter().map(|value| value.parse().unwrap()).collect();
+        let mean = z_scores.iter().sum::<f64>() / z_scores.len() as f64;
+        let std_dev = (z_scores.iter().map(|z| (z - mean).powi(2)).sum::<f64>() / z_scores.len() as f64).sqrt();
+        assert!(mean.abs() < 1e-5, "{} mean {}", name, mean);
+        assert!((std_dev - 1.0).abs() < 1e-5, "{} std dev {}", name, std_dev);
+    }
+    // The extremes stay raw.
+    assert_eq!(column(&summary, "max temp"), vec!["60", "70", "65", "81"]);
+
+    let (output, summary) = summarize("no-normalize", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg temp"), vec!["60", "70", "65", "81"]);
+}
+
+#[test]
+fn the_row_tally_counts_processed_and_skipped_rows() {
+    let input = format!(
+        "{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 12:00:00,hot,40,35,1.0\n2021-01-01 18:00:00,70,600,45,1.4\n2021-01-02 06:00:00,62,40,35,1.0\n2021-01-02 12:00:00,64,40,35,1.0\n",
+        HEADER,
+    );
+    let (output, summary) = summarize("row-tally", &input, &["--report-errors"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Processed 3 of 5 rows; 2 skipped (1 parse errors, 1 out-of-range).\n"), "{}", stderr(&output));
+    assert_eq!(column(&summary, "avg temp"), vec!["60", "63"]);
+
+    // Rows left out by sampling, the hour window or the max date are skipped for those reasons.
+    let dir = scratch_dir("row-tally-filters");
+    let input = format!(
+        "{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 07:00:00,61,40,35,1.0\n2021-01-01 22:00:00,62,40,35,1.0\n2021-01-01 23:00:00,63,40,35,1.0\n2021-01-02 08:00:00,64,40,35,1.0\n",
+        HEADER,
+    );
+    fs::write(dir.join("input.csv"), input).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--sample-every", "2", "--between-hours", "06-20", "--max-date", "2021-01-01", "--drop-future", "--report-json", "report.json"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("Processed 1 of 5 rows; 4 skipped (0 parse errors, 0 out-of-range, 2 sampled out, 1 outside the hours, 1 dated in the future).\n"), "{}", stderr(&output));
+    let report = fs::read_to_string(dir.join("report.json")).unwrap();
+    assert!(report.contains("\"tally\":{\"rows\":5,\"processed\":1,\"skipped\":4,\"parse_errors\":0,\"out_of_range\":0,\"flagged_bad\":0,\"out_of_order\":0,\"sampled_out\":2,\"outside_hours\":1,\"dropped_future\":1}"), "{}", report);
+}
+
+#[test]
+fn round_dates_to_week_labels_mid_week_days_with_their_monday() {
+    // A Wednesday and a Thursday.
+    let input = format!("{}2021-06-02 06:00:00,60,40,35,1.0\n2021-06-03 06:00:00,70,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("round-dates-to-week", &input, &["--round-dates-to", "week"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "date"), vec!["2021-05-31", "2021-05-31"]);
+    // Only the label changes: each day keeps its own row.
+    assert_eq!(column(&summary, "avg temp"), vec!["60", "70"]);
+
+    let (output, summary) = summarize("round-dates-to-month", &input, &["--round-dates-to", "month"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "date"), vec!["2021-06-01", "2021-06-01"]);
+}
+
+#[test]
+fn ewma_converges_on_a_step_change_at_the_rate_of_alpha() {
+    let days: String = [50, 50, 70, 70, 70, 70].iter().enumerate()
+        .map(|(day, temperature)| format!("2021-01-{:02} 06:00:00,{},40,35,1.0\n", day + 1, temperature))
+        .collect();
+    let (output, summary) = summarize("ewma", &format!("{}{}", HEADER, days), &["--ewma-alpha", "0.5"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // It starts at the first day's mean, then halves the remaining gap to 70 each day.
+    assert_eq!(column(&summary, "ewma temp"), vec!["50", "50", "60", "65", "67.5", "68.75"]);
+}
+
+#[test]
+fn watch_reprocesses_the_input_when_it_changes() {
+    let dir = scratch_dir("watch");
+    let first_days = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER);
+    fs::write(dir.join("input.csv"), &first_days).unwrap();
+    let mut watcher = Command::new(env!("CARGO_BIN_EXE_sensor-data"))
+        .current_dir(&dir)
+        .args(["--input", "input.csv", "--output", "out.csv", "--watch"])
+        .stdout(Stdio::null())
+        .stderr(fs::File::create(dir.join("stderr.txt")).unwrap())
+        .spawn()
+        .unwrap();
+    // Waits up to ten seconds for the summary to be `expected`.
+    let wait_for_summary = |expected: &str| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if fs::read_to_string(dir.join("out.csv")).unwrap_or_default() == expected {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        false
+    };
+
+    // The second day gets another reading along with the third, so its row is written again; the
+    // re-run ends up with the same summary as a single run over all three days.
+    let all_days = format!("{}2021-01-02 18:00:00,70,40,35,1.0\n2021-01-03 06:00:00,64,40,35,1.0\n", first_days);
+    let (_, first_summary) = summarize("watch-first-days", &first_days, &[]);
+    let (_, all_summary) = summarize("watch-all-days", &all_days, &[]);
+    let first_run = wait_for_summary(&first_summary);
+    fs::write(dir.join("input.csv"), &all_days).unwrap();
+    let rerun = first_run && wait_for_summary(&all_summary);
+    watcher.kill().unwrap();
+    watcher.wait().unwrap();
+    assert!(first_run, "the first run never wrote its summary");
+    assert!(rerun, "the change to the input didn't trigger a re-run");
+    assert!(fs::read_to_string(dir.join("stderr.txt")).unwrap().contains("resuming after 2021-01-01"));
+}
+
+// Interrupts a run partway through with a real SIGINT, then resumes it. The run writes its clean
+// copy of the input to a fifo, so it can only get as far ahead of the test as the pipe buffers;
+// the test reads the first days from it before sending the signal.
+#[cfg(unix)]
+#[test]
+fn an_interrupted_run_checkpoints_and_resumes_with_its_running_totals() {
+    let mut input = HEADER.to_string();
+    for day in 1..=10 {
+        for reading in 0..1920 {
+            let seconds = reading * 45;
+            let temperature = 50.0 + day as f32 * 3.0 + (reading % 60) as f32 / 10.0;
+            input += &format!("2021-06-{:02} {:02}:{:02}:{:02},{},50,40,1.0\n", day, seconds / 3600, seconds / 60 % 60, seconds % 60, temperature);
+        }
+    }
+    let args = ["--input", "input.csv", "--output", "out.csv", "--gdd-base", "50,40", "--degree-day-base", "60", "--ewma-alpha", "0.5"];
+    let dir = scratch_dir("uninterrupted");
+    fs::write(dir.join("input.csv"), &input).unwrap();
+    let output = run(&dir, &args);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let uninterrupted = fs::read_to_string(dir.join("out.csv")).unwrap();
+
+    let dir = scratch_dir("interrupted");
+    fs::write(dir.join("input.csv"), &input).unwrap();
+    let fifo = std::ffi::CString::new(dir.join("clean.csv").to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+    let interrupted = Command::new(env!("CARGO_BIN_EXE_sensor-data"))
+        .current_dir(&dir)
+        .args(args.iter().chain(&["--clean-output", "clean.csv"]))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut clean = io::BufReader::new(fs::File::open(dir.join("clean.csv")).unwrap());
+    let mut line = String::new();
+    while !line.starts_with("2021-06-04") {
+        line.clear();
+        assert!(clean.read_line(&mut line).unwrap() > 0, "the run ended before it was interrupted");
+    }
+    assert_eq!(unsafe { libc::kill(interrupted.id() as libc::pid_t, libc::SIGINT) }, 0);
+    io::copy(&mut clean, &mut io::sink()).unwrap();
+    let output = interrupted.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130), "{}", stderr(&output));
+
+    let checkpoint = fs::read_to_string(dir.join("out.csv.checkpoint")).unwrap();
+    let partial = fs::read_to_string(dir.join("out.csv")).unwrap();
+    let last_row = partial.lines().last().unwrap();
+    assert!(partial.lines().count() < 11, "the run wasn't interrupted before it finished");
+    assert!(checkpoint.starts_with(last_row.split(',').next().unwrap()), "{}", checkpoint);
+    assert!(checkpoint.contains("total_gdd="), "{}", checkpoint);
+
+    let output = run(&dir, &args.iter().chain(&["--resume"]).copied().collect::<Vec<&str>>());
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(fs::read_to_string(dir.join("out.csv")).unwrap(), uninterrupted);
+    assert!(!dir.join("out.csv.checkpoint").exists());
+}
+
+// Runs over a two-day input, writing to `output`, and returns the run and what it wrote there.
+fn summarize_to(test: &str, output: &str, args: &[&str]) -> (Output, String) {
+    let dir = scratch_dir(test);
+    let input = format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-02 06:00:00,62,40,35,1.0\n", HEADER);
+    fs::write(dir.join("input.csv"), input).unwrap();
+    let mut all_args = vec!["--input", "input.csv", "--output", output];
+    all_args.extend(args);
+    let run = run(&dir, &all_args);
+    let written = fs::read_to_string(dir.join(output)).unwrap_or_default();
+    (run, written)
+}
+
+#[test]
+fn output_format_and_delimiter_follow_the_output_extension() {
+    let (output, tsv) = summarize_to("extension-tsv", "out.tsv", &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(tsv.starts_with("date\tavg temp\tmax temp\t"), "{}", tsv);
+    assert!(tsv.lines().nth(1).unwrap().starts_with("2021-01-01\t60\t"), "{}", tsv);
+    assert!(!tsv.contains(','));
+
+    let (output, json) = summarize_to("extension-json", "out.json", &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let lines: Vec<&str> = json.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", json);
+    assert!(lines[0] == "[" && lines[3] == "]", "{}", json);
+    assert!(lines[1].starts_with("{\"date\":\"2021-01-01\"") && lines[1].ends_with("},"), "{}", json);
+    assert!(lines[2].starts_with("{\"date\":\"2021-01-02\"") && lines[2].ends_with('}'), "{}", json);
+
+    let (output, json_lines) = summarize_to("extension-jsonl", "out.jsonl", &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(json_lines.lines().count(), 2);
+    assert!(json_lines.lines().all(|line| line.starts_with("{\"date\":") && line.ends_with('}')), "{}", json_lines);
+
+    // An explicit flag wins over the extension.
+    let (output, csv) = summarize_to("extension-overridden", "out.tsv", &["--out-delimiter", ";"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(csv.starts_with("date;avg temp;"), "{}", csv);
+    let (output, csv) = summarize_to("format-overridden", "out.json", &["--format", "csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(csv.starts_with("date,avg temp,"), "{}", csv);
+}
+
+#[test]
+fn each_gdd_base_accumulates_in_its_own_column() {
+    let input = format!("{}2021-05-01 06:00:00,55,40,35,1.0\n2021-05-02 06:00:00,60,40,35,1.0\n2021-05-03 06:00:00,45,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("gdd-bases", &input, &["--gdd-base", "50,40"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // The 45°F day is below the corn base but still adds to the pea base's total.
+    assert_eq!(column(&summary, "gdd 50"), vec!["5", "15", "10"]);
+    assert_eq!(column(&summary, "gdd 40"), vec!["15", "35", "40"]);
+    // The first base remains the main one.
+    assert_eq!(column(&summary, "gdd"), column(&summary, "gdd 50"));
+}
+
+#[test]
+fn a_directory_of_daily_files_is_summarized_as_one_stream() {
+    let dir = scratch_dir("daily-files");
+    fs::create_dir(dir.join("daily")).unwrap();
+    // Named out of order and in both date styles, so the order comes from the names' dates.
+    fs::write(dir.join("daily/2021-01-03.csv"), format!("{}2021-01-03 06:00:00,64,40,35,1.0\n", HEADER)).unwrap();
+    fs::write(dir.join("daily/20210101.csv"), format!("{}2021-01-01 06:00:00,60,40,35,1.0\n2021-01-01 18:00:00,70,40,35,1.0\n", HEADER)).unwrap();
+    fs::write(dir.join("daily/2021-01-02.csv"), format!("{}2021-01-02 06:00:00,62,40,35,1.0\n", HEADER)).unwrap();
+    fs::write(dir.join("daily/notes.txt"), "not sensor data").unwrap();
+    let output = run(&dir, &["--input", "daily", "--output", "out.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    assert_eq!(column(&summary, "date"), vec!["2021-01-01", "2021-01-02", "2021-01-03"]);
+    assert_eq!(column(&summary, "avg temp"), vec!["65", "62", "64"]);
+
+    fs::write(dir.join("daily/backup.csv"), HEADER).unwrap();
+    let output = run(&dir, &["--input", "daily", "--output", "out.csv"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("File name \"backup.csv\" in daily is not a date"), "{}", stderr(&output));
+}
+
+#[test]
+fn show_raw_gdd_shows_a_cold_days_negative_raw_and_zero_floored_gdd() {
+    let input = format!("{}2021-04-01 06:00:00,60,40,35,1.0\n2021-04-01 18:00:00,90,40,35,1.0\n2021-04-02 06:00:00,40,40,35,1.0\n2021-04-02 18:00:00,50,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("show-raw-gdd", &input, &["--gdd-method", "min-max", "--gdd-base", "50", "--show-raw-gdd"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "raw gdd"), vec!["25", "-5"]);
+    assert_eq!(column(&summary, "gdd contribution"), vec!["25", "0"]);
+    // Only the floored contribution accumulates.
+    assert_eq!(column(&summary, "gdd"), vec!["25", "25"]);
+
+    // The default mean method doesn't floor, so the cold day's contribution is its raw gdd.
+    let (output, summary) = summarize("show-raw-gdd-mean", &input, &["--gdd-base", "50", "--show-raw-gdd"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "raw gdd"), vec!["25", "-5"]);
+    assert_eq!(column(&summary, "gdd contribution"), vec!["25", "-5"]);
+    assert_eq!(column(&summary, "gdd"), vec!["25", "20"]);
+
+    let (output, summary) = summarize("no-raw-gdd", &input, &["--gdd-method", "min-max", "--gdd-base", "50"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!summary.lines().next().unwrap().contains("raw gdd"), "{}", summary);
+}
+
+#[test]
+fn diurnal_writes_a_24_point_typical_day() {
+    let dir = scratch_dir("diurnal");
+    let mut input = HEADER.to_string();
+    for (day, offset, humidity) in &[(1, 50, 40), (2, 54, 60)] {
+        for hour in 0..24 {
+            // The dew point sensor drops out at 03:00 on both days.
+            let dew_point = if hour == 3 { "NA".to_string() } else { (30 + offset - 50).to_string() };
+            input += &format!("2021-06-{:02} {:02}:00:00,{},{},{},1.0\n", day, hour, hour + offset, humidity, dew_point);
+        }
+    }
+    fs::write(dir.join("input.csv"), input).unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--diurnal", "diurnal.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let diurnal = fs::read_to_string(dir.join("diurnal.csv")).unwrap();
+    assert_eq!(diurnal.lines().next(), Some("hour,avg temp,avg humidity,avg dewpoint,avg vpd"));
+    let expected: Vec<String> = (0..24).map(|hour| format!("{},{},50,{},1", hour, hour + 52, if hour == 3 { "" } else { "32" })).collect();
+    assert_eq!(diurnal.lines().skip(1).collect::<Vec<&str>>(), expected);
+}
+
+#[test]
+fn gdd_settings_columns_record_the_configured_method_and_base() {
+    let input = format!("{}2021-05-01 06:00:00,60,40,35,1.0\n2021-05-02 06:00:00,62,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("gdd-settings-columns", &input, &["--gdd-settings-columns", "--gdd-method", "base-floor", "--gdd-base", "41"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "gdd method"), vec!["base-floor", "base-floor"]);
+    assert_eq!(column(&summary, "gdd base"), vec!["41", "41"]);
+
+    let (output, summary) = summarize("crop-gdd-settings-columns", &input, &["--gdd-settings-columns", "--crop", "wheat"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!((column(&summary, "gdd method"), column(&summary, "gdd base")), (vec!["mean".to_string(); 2], vec!["32".to_string(); 2]));
+
+    let (output, summary) = summarize("no-gdd-settings-columns", &input, &[]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!summary.lines().next().unwrap().contains("gdd method"), "{}", summary);
+}
+
+#[test]
+fn baseline_anomalies_are_each_days_departure_from_its_normal() {
+    let dir = scratch_dir("baseline");
+    fs::write(dir.join("input.csv"), format!("{}2021-06-01 06:00:00,62,40,35,1.0\n2021-06-02 06:00:00,70,40,35,1.0\n", HEADER)).unwrap();
+    // June 1st is day 153 of the 366-day calendar; June 2nd has no normals, and June 1st no dew point normal.
+    fs::write(dir.join("normals.csv"), "day of year,avg temp,avg humidity,avg dewpoint,avg vpd\n153,60,45,,0.5\n").unwrap();
+    let output = run(&dir, &["--input", "input.csv", "--output", "out.csv", "--baseline", "normals.csv"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let summary = fs::read_to_string(dir.join("out.csv")).unwrap();
+    assert_eq!(column(&summary, "temp anomaly"), vec!["2", ""]);
+    assert_eq!(column(&summary, "humidity anomaly"), vec!["-5", ""]);
+    assert_eq!(column(&summary, "dewpoint anomaly"), vec!["", ""]);
+    assert_eq!(column(&summary, "vpd anomaly"), vec!["0.5", ""]);
+}
+
+#[test]
+fn gdd_percent_of_target_tracks_the_accumulation_and_caps_at_100() {
+    // 20 gdd a day towards a target of 50.
+    let input = format!("{}2021-06-01 06:00:00,70,40,35,1.0\n2021-06-02 06:00:00,70,40,35,1.0\n2021-06-03 06:00:00,70,40,35,1.0\n", HEADER);
+    let (output, summary) = summarize("gdd-target", &input, &["--gdd-base", "50", "--gdd-target", "50"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "gdd"), vec!["20", "40", "60"]);
+    assert_eq!(column(&summary, "gdd % of target"), vec!["40", "80", "100"]);
+
+    let (output, summary) = summarize("gdd-target-overshoot", &input, &["--gdd-base", "50", "--gdd-target", "50", "--gdd-target-overshoot"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(column(&summary, "gdd % of target"), vec!["40", "80", "120"]);
+}
+
+#[test]
+fn three_stable_days_collapse_into_one_aggregated_row() {
+    let readings = [("2021-01-01", 60.0, 62.0), ("2021-01-02", 61.0, 63.0), ("2021-01-03", 60.5, 61.5), ("2021-01-04", 70.0, 72.0)];
+    let mut input = HEADER.to_string();
+    for (date, morning, evening) in &readings {
+        input += &format!("{} 06:00:00,{},50,40,1.0\n{} 18:00:00,{},50,40,1.0\n", date, morning, date, evening);
+    }
+    let (output, summary) = summarize("collapse-stable", &input, &["--collapse-stable", "2", "--gdd-base", "50", "--round", "2"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let rows: Vec<&str> = summary.lines().skip(1).collect();
+    assert_eq!(rows, vec![
+        "2021-01-01/2021-01-03,61.33,63.00,60.00,50.00,50.00,50.00,40.00,1.00,34.00,",
+        "2021-01-04,71.00,72.00,70.00,50.00,50.00,50.00,40.00,1.00,55.00,",
+    ]);
+}